@@ -0,0 +1,77 @@
+//! Message lookup benchmarks: `cargo bench --bench lookup`
+//!
+//! Compares walking a JSON tree of the messages (as returned by `Dictionary::lookup`) with the
+//! flattened message index (`Dictionary::message`), and times `translate` (and its variants) end
+//! to end.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use loon::prelude::*;
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn bench<F: FnMut()>(name: &str, mut f: F) -> Duration {
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!("{:<40} {:>8.1} ns/iter", name, elapsed.as_nanos() as f64 / f64::from(ITERATIONS));
+    elapsed
+}
+
+/// Walk `value` along `path`.
+fn dig<'v, 'p>(
+    value: &'v serde_json::Value,
+    mut path: impl Iterator<Item = &'p str>,
+) -> Option<&'v str> {
+    path.try_fold(value, |value, part| value.get(part))?.as_str()
+}
+
+fn main() {
+    let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    let key = "a.very.nested.message";
+    let path = ["a", "very", "nested", "message"];
+    let root = serde_json::json!({ "a": dict.lookup("en", "a").unwrap() });
+
+    let tree = bench("tree walk (&str)", || {
+        black_box(dig(&root, black_box(key).split('.')));
+    });
+    let index = bench("flattened index (message, &str)", || {
+        black_box(dict.message("en", black_box(key)));
+    });
+    println!("{:<40} {:>8.1}x", "speedup", tree.as_secs_f64() / index.as_secs_f64());
+
+    bench("tree walk (&[&str])", || {
+        black_box(dig(&root, black_box(&path).iter().copied()));
+    });
+    bench("flattened index (message, &[&str])", || {
+        black_box(dict.message("en", black_box(&path)));
+    });
+
+    bench("translate (plain)", || {
+        black_box(dict.translate(black_box("greeting"), None).unwrap());
+    });
+    bench("translate_cow (plain)", || {
+        black_box(dict.translate_cow(black_box("greeting"), None).unwrap());
+    });
+    let mut out = String::new();
+    bench("translate_to (plain, reused buffer)", || {
+        out.clear();
+        dict.translate_to(&mut out, black_box("greeting"), None).unwrap();
+        black_box(&out);
+    });
+    bench("translate (plural)", || {
+        black_box(dict.translate(black_box("messages"), Count(3)).unwrap());
+    });
+    bench("translate (vars)", || {
+        let opts = Opts::default().var("name", "Jacob").var("message", "Hi");
+        black_box(dict.translate(black_box(key), opts).unwrap());
+    });
+}
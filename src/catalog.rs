@@ -0,0 +1,355 @@
+//! Compact binary catalogs: the flattened messages of a `Dictionary` as a string table and a
+//! sorted key index per locale, loaded without deserializing a tree (and memory-mapped with the
+//! `mmap` feature).
+//!
+//! Layout (integers are little-endian `u32`s, strings are `offset, length` pairs into the file):
+//!
+//! ```text
+//! header:   b"LOONCAT\0", version, default locale, locale count
+//! locales:  name, syntax, entry table offset, entry count
+//! entries:  key, kind (0 message, 1 map, 2 list, 3 other value), value    (sorted by key)
+//! strings:  UTF-8 text; the value of a map or list is its child names, `\0`-separated, and
+//!           other values (numbers, booleans, nulls) are JSON
+//! ```
+
+use std::{collections::HashMap, convert::TryFrom, fmt, io, path::Path, sync::Arc};
+
+use super::{
+    err,
+    index::{Branch, Message, Node, Parsed, Syntax},
+    interpolate::Interpolation,
+    Dictionary,
+};
+
+const MAGIC: &[u8; 8] = b"LOONCAT\0";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 24;
+const LOCALE_LEN: usize = 20;
+const ENTRY_LEN: usize = 20;
+
+const MESSAGE: u32 = 0;
+const BRANCH: u32 = 1;
+const LIST: u32 = 2;
+const VALUE: u32 = 3;
+
+/// The bytes of a catalog, read into memory or memory-mapped
+#[derive(Clone)]
+pub(crate) struct Data(Arc<dyn AsRef<[u8]> + Send + Sync>);
+
+impl Data {
+    fn bytes(&self) -> &[u8] {
+        (*self.0).as_ref()
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        let bytes = self.bytes().get(pos..pos + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// The bytes of the string referenced at `pos`.
+    fn bytes_at(&self, pos: usize) -> Option<&[u8]> {
+        let offset = self.u32_at(pos)? as usize;
+        let len = self.u32_at(pos + 4)? as usize;
+        self.bytes().get(offset..offset.checked_add(len)?)
+    }
+
+    /// The string referenced at `pos`.
+    fn str_at(&self, pos: usize) -> Option<&str> {
+        std::str::from_utf8(self.bytes_at(pos)?).ok()
+    }
+}
+
+impl fmt::Debug for Data {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Data({} bytes)", self.bytes().len())
+    }
+}
+
+fn invalid<T: fmt::Display>(t: T) -> err::Error {
+    err::custom(format!("Invalid catalog: {}", t))
+}
+
+fn encode_syntax(syntax: Syntax) -> u32 {
+    match syntax {
+        Syntax::Strfmt(Interpolation::Brace) => 0,
+        Syntax::Strfmt(Interpolation::Percent) => 1,
+        Syntax::Strfmt(Interpolation::DoubleBrace) => 2,
+        Syntax::Icu => 3,
+    }
+}
+
+fn decode_syntax(code: u32) -> Option<Syntax> {
+    match code {
+        0 => Some(Syntax::Strfmt(Interpolation::Brace)),
+        1 => Some(Syntax::Strfmt(Interpolation::Percent)),
+        2 => Some(Syntax::Strfmt(Interpolation::DoubleBrace)),
+        3 => Some(Syntax::Icu),
+        _ => None,
+    }
+}
+
+/// The messages of a locale in a catalog
+#[derive(Debug)]
+pub(crate) struct Locale {
+    data: Data,
+    syntax: Syntax,
+    /// Offset of the entry table
+    entries: usize,
+    /// The parsed messages, by entry (`None` for maps of messages and other values)
+    parsed: Box<[Option<Parsed>]>,
+}
+
+impl Locale {
+    pub(crate) fn syntax(&self) -> Syntax {
+        self.syntax
+    }
+
+    fn node(&self, i: usize) -> Option<(&str, Node<'_>)> {
+        let pos = self.entries + i * ENTRY_LEN;
+        let key = self.data.str_at(pos)?;
+        let value = self.data.str_at(pos + 12)?;
+        let node = match self.data.u32_at(pos + 8)? {
+            BRANCH => Node::Branch(Branch::Joined(value)),
+            LIST => Node::List(Branch::Joined(value)),
+            VALUE => Node::Value(value),
+            _ => Node::Message(Message { text: value, parsed: self.parsed[i].as_ref()? }),
+        };
+        Some((key, node))
+    }
+
+    /// Binary search the entries for `path`.
+    pub(crate) fn get(&self, path: &str) -> Option<Node<'_>> {
+        let (mut lo, mut hi) = (0, self.parsed.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let key = self.data.bytes_at(self.entries + mid * ENTRY_LEN)?;
+            match key.cmp(path.as_bytes()) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return self.node(mid).map(|(_, node)| node),
+            }
+        }
+        None
+    }
+
+    pub(crate) fn entries(&self) -> Vec<(&str, Node<'_>)> {
+        (0..self.parsed.len()).filter_map(|i| self.node(i)).collect()
+    }
+}
+
+/// A loaded catalog
+pub(crate) struct Catalog {
+    pub(crate) default_locale: String,
+    pub(crate) locales: Vec<(String, Locale)>,
+}
+
+/// Check the catalog in `data` and read its locale table.
+///
+/// Every string is checked and every message parsed here, so lookups can't fail on a truncated or
+/// corrupt file. Syntax errors are kept for the messages that have them.
+pub(crate) fn read(data: Data) -> err::Result<Catalog> {
+    let bytes = data.bytes();
+    if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+        return Err(invalid("not a loon catalog"));
+    }
+    let version = data.u32_at(8).unwrap_or_default();
+    if version != VERSION {
+        return Err(invalid(format!("unsupported version {}", version)));
+    }
+    let default_locale =
+        data.str_at(12).ok_or_else(|| invalid("default locale out of bounds"))?.to_string();
+    let count = data.u32_at(20).unwrap_or_default() as usize;
+
+    let mut locales = Vec::new();
+    for i in 0..count {
+        let pos = HEADER_LEN + i * LOCALE_LEN;
+        let name = data.str_at(pos).ok_or_else(|| invalid("locale table out of bounds"))?;
+        let syntax = data
+            .u32_at(pos + 8)
+            .and_then(decode_syntax)
+            .ok_or_else(|| invalid(format!("unknown syntax for {}", name)))?;
+        let entries = data.u32_at(pos + 12).unwrap_or_default() as usize;
+        let len = data.u32_at(pos + 16).unwrap_or_default() as usize;
+        let table_end = len.checked_mul(ENTRY_LEN).and_then(|size| entries.checked_add(size));
+        if table_end.filter(|&end| end <= bytes.len()).is_none() {
+            return Err(invalid(format!("entry table of {} out of bounds", name)));
+        }
+
+        let mut parsed = Vec::with_capacity(len);
+        for j in 0..len {
+            let entry = entries + j * ENTRY_LEN;
+            let (key, value) = match (data.str_at(entry), data.str_at(entry + 12)) {
+                (Some(key), Some(value)) => (key, value),
+                _ => return Err(invalid(format!("entry {} of {} out of bounds", j, name))),
+            };
+            parsed.push(match data.u32_at(entry + 8) {
+                Some(MESSAGE) => Some(syntax.compile(name, key, value)),
+                Some(BRANCH) | Some(LIST) | Some(VALUE) => None,
+                _ => return Err(invalid(format!("unknown kind of entry {} of {}", j, name))),
+            });
+        }
+
+        let parsed = parsed.into_boxed_slice();
+        let locale = Locale { data: data.clone(), syntax, entries, parsed };
+        locales.push((name.to_string(), locale));
+    }
+
+    Ok(Catalog { default_locale, locales })
+}
+
+/// Read the catalog file at `path`, memory-mapped with the `mmap` feature.
+pub(crate) fn open(path: &Path) -> err::Result<Catalog> {
+    let file = std::fs::File::open(path)?;
+
+    #[cfg(feature = "mmap")]
+    // Safety: the mapping is read only, and catalogs are not modified while in use (replace the
+    // file with a new one instead).
+    let data = Data(Arc::new(unsafe { memmap2::Mmap::map(&file)? }));
+
+    #[cfg(not(feature = "mmap"))]
+    let data = {
+        use std::io::Read;
+        let mut buffer = Vec::new();
+        (&file).read_to_end(&mut buffer)?;
+        Data(Arc::new(buffer))
+    };
+
+    read(data)
+}
+
+/// Strings of a catalog, each written once
+#[derive(Default)]
+struct Strings {
+    bytes: Vec<u8>,
+    offsets: HashMap<String, usize>,
+}
+
+impl Strings {
+    /// Add `s`, returning its `(offset, length)` within the table.
+    fn add(&mut self, s: &str) -> (usize, usize) {
+        let bytes = &mut self.bytes;
+        let offset = *self.offsets.entry(s.to_string()).or_insert_with(|| {
+            bytes.extend_from_slice(s.as_bytes());
+            bytes.len() - s.len()
+        });
+        (offset, s.len())
+    }
+}
+
+/// Write the messages of `dict` as a catalog to `out`.
+pub(crate) fn write<W: io::Write>(dict: &Dictionary, mut out: W) -> err::Result<()> {
+    let mut strings = Strings::default();
+    let mut locales = dict.index.iter().collect::<Vec<_>>();
+    locales.sort_unstable_by_key(|(locale, _)| locale.as_str());
+
+    let default_locale = strings.add(&dict.default_locale);
+    let mut locale_table = Vec::new();
+    let mut entry_table = Vec::new();
+    for (locale, index) in &locales {
+        let entries = index.entries();
+        locale_table.push((strings.add(locale), index.syntax(), entry_table.len(), entries.len()));
+        for (key, node) in entries {
+            let key = strings.add(key);
+            let (kind, value) = match node {
+                Node::Message(message) => (MESSAGE, strings.add(message.text)),
+                Node::Branch(branch) => (BRANCH, strings.add(&branch.joined())),
+                Node::List(branch) => (LIST, strings.add(&branch.joined())),
+                Node::Value(value) => (VALUE, strings.add(value)),
+            };
+            entry_table.push((key, kind, value));
+        }
+    }
+
+    let entries_start = HEADER_LEN + locale_table.len() * LOCALE_LEN;
+    let strings_start = entries_start + entry_table.len() * ENTRY_LEN;
+
+    let mut buf = Vec::with_capacity(strings_start + strings.bytes.len());
+    let mut put = |n: usize| -> err::Result<()> {
+        let n = u32::try_from(n).map_err(|_| err::custom("Catalog is too large"))?;
+        buf.extend_from_slice(&n.to_le_bytes());
+        Ok(())
+    };
+
+    put(VERSION as usize)?;
+    put(strings_start + default_locale.0)?;
+    put(default_locale.1)?;
+    put(locale_table.len())?;
+    for ((offset, len), syntax, first, count) in locale_table {
+        put(strings_start + offset)?;
+        put(len)?;
+        put(encode_syntax(syntax) as usize)?;
+        put(entries_start + first * ENTRY_LEN)?;
+        put(count)?;
+    }
+    for ((key, key_len), kind, (value, value_len)) in entry_table {
+        put(strings_start + key)?;
+        put(key_len)?;
+        put(kind as usize)?;
+        put(strings_start + value)?;
+        put(value_len)?;
+    }
+
+    out.write_all(MAGIC)?;
+    out.write_all(&buf)?;
+    out.write_all(&strings.bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{read, write, Data, HEADER_LEN};
+    use crate::{err, index::Index, Config};
+    use std::sync::Arc;
+
+    #[test]
+    fn round_trips() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+        let mut bytes = Vec::new();
+        write(&dict, &mut bytes).unwrap();
+
+        let catalog = read(Data(Arc::new(bytes.clone()))).unwrap();
+        assert_eq!(catalog.default_locale, "en");
+        assert_eq!(
+            catalog.locales.iter().map(|(locale, _)| locale.as_str()).collect::<Vec<_>>(),
+            vec!["de", "en", "ru"]
+        );
+
+        for (name, locale) in catalog.locales {
+            let index = Index::Catalog(locale);
+            let expected = &dict.index[&name];
+            let paths = |index: &Index| {
+                index.entries().into_iter().map(|(path, _)| path.to_string()).collect::<Vec<_>>()
+            };
+            assert_eq!(paths(&index), paths(expected));
+            for (path, _) in expected.entries() {
+                let text = |index: &Index| index.message(path).map(|m| m.text.to_string());
+                assert_eq!(text(&index), text(expected));
+            }
+        }
+
+        let index =
+            Index::Catalog(read(Data(Arc::new(bytes.clone()))).unwrap().locales.remove(1).1);
+        assert!(index.branch("messages").unwrap().contains("zero"));
+        assert!(index.message("missing.key").is_none());
+
+        assert!(read(Data(Arc::new(bytes[..bytes.len() - 1].to_vec()))).is_err());
+
+        // A corrupt entry count is caught before allocating
+        let mut corrupt = bytes.clone();
+        corrupt[HEADER_LEN + 16..HEADER_LEN + 20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read(Data(Arc::new(corrupt))).err().unwrap().to_string().contains("out of bounds"));
+
+        // Syntax errors are kept for the message that has them
+        let pos = bytes.windows(6).position(|w| w == b"{name}").unwrap();
+        bytes[pos + 5] = b' ';
+        let errors = read(Data(Arc::new(bytes)))
+            .unwrap()
+            .locales
+            .into_iter()
+            .filter_map(|(_, locale)| Index::Catalog(locale).check().err())
+            .collect::<Vec<_>>();
+        assert!(matches!(errors[..], [err::Error::MessageFormat(_)]));
+        assert!(read(Data(Arc::new(b"not a catalog at all....".to_vec()))).is_err());
+    }
+}
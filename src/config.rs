@@ -1,5 +1,15 @@
-use super::{err, Dictionary};
-use std::path::PathBuf;
+use super::{
+    catalog, err,
+    index::{Index, Syntax},
+    interpolate::Warn,
+    plural::CustomRule,
+    Dictionary, PluralOperands,
+};
+pub use super::{
+    interpolate::{Interpolation, MissingVar},
+    pseudo::PseudoLocale,
+};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 /// Helper for setting `default_locale` configuration
 pub struct DefaultLocale<T>(pub T);
@@ -7,6 +17,26 @@ pub struct DefaultLocale<T>(pub T);
 pub struct PathPattern<T>(pub T);
 /// Helper for setting `localized_path` configuration
 pub struct LocalizedPath<T, U>(pub T, pub U);
+/// Helper for setting `catalog` configuration
+pub struct CatalogPath<T>(pub T);
+/// Helper for setting `plural_rule` configuration
+pub struct PluralRule<T, F>(pub T, pub F);
+/// Helper for setting `missing_var_hook` configuration
+pub struct MissingVarHook<F>(pub F);
+
+/// Syntax of the messages in the translation files (also a helper for setting `message_format`
+/// configuration)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// `{name}` placeholders, formatted with `strfmt` (the default)
+    #[default]
+    Strfmt,
+    /// ICU MessageFormat: `{count, plural, one {# item} other {# items}}`, `select`, etc.
+    ///
+    /// Messages are parsed when the `Dictionary` is built. A syntax error is reported when the
+    /// message is used, or by `Config::finish` with `Config::with_strict`.
+    Icu,
+}
 
 pub trait ConfigPart {
     fn add_to(self, config: Config) -> Config;
@@ -40,6 +70,58 @@ where
     }
 }
 
+impl<T> ConfigPart for CatalogPath<T>
+where
+    T: Into<PathBuf>,
+{
+    fn add_to(self, config: Config) -> Config {
+        config.with_catalog(self.0)
+    }
+}
+
+impl<T, F> ConfigPart for PluralRule<T, F>
+where
+    T: Into<String>,
+    F: Fn(&PluralOperands) -> &'static str + Send + Sync + 'static,
+{
+    fn add_to(self, config: Config) -> Config {
+        config.with_plural_rule(self.0, self.1)
+    }
+}
+
+impl ConfigPart for MessageFormat {
+    fn add_to(self, config: Config) -> Config {
+        config.with_message_format(self)
+    }
+}
+
+impl ConfigPart for Interpolation {
+    fn add_to(self, config: Config) -> Config {
+        config.with_interpolation(self)
+    }
+}
+
+impl ConfigPart for MissingVar {
+    fn add_to(self, config: Config) -> Config {
+        config.with_missing_var(self)
+    }
+}
+
+impl ConfigPart for PseudoLocale {
+    fn add_to(self, config: Config) -> Config {
+        config.with_pseudo_locale(self)
+    }
+}
+
+impl<F> ConfigPart for MissingVarHook<F>
+where
+    F: Fn(&str, &str, &str) + Send + Sync + 'static,
+{
+    fn add_to(self, config: Config) -> Config {
+        config.with_missing_var_hook(self.0)
+    }
+}
+
 impl<T> ConfigPart for (T,)
 where
     T: ConfigPart,
@@ -49,7 +131,7 @@ where
     }
 }
 
-impl<'a, T, U> ConfigPart for (T, U)
+impl<T, U> ConfigPart for (T, U)
 where
     T: ConfigPart,
     U: ConfigPart,
@@ -59,7 +141,7 @@ where
     }
 }
 
-impl<'a, T, U, V> ConfigPart for (T, U, V)
+impl<T, U, V> ConfigPart for (T, U, V)
 where
     T: ConfigPart,
     U: ConfigPart,
@@ -71,7 +153,7 @@ where
     }
 }
 
-impl<'a, T, U, V, W> ConfigPart for (T, U, V, W)
+impl<T, U, V, W> ConfigPart for (T, U, V, W)
 where
     T: ConfigPart,
     U: ConfigPart,
@@ -98,7 +180,15 @@ where
 pub struct Config {
     load_paths: Vec<(Option<String>, PathBuf)>,
     load_path_pattern: Option<String>,
+    catalogs: Vec<PathBuf>,
     default_locale: Option<String>,
+    plural_rules: HashMap<String, CustomRule>,
+    message_format: MessageFormat,
+    interpolation: Interpolation,
+    missing_var: Option<MissingVar>,
+    missing_var_hook: Option<Warn>,
+    pseudo_locales: Vec<PseudoLocale>,
+    strict: bool,
 }
 
 impl Config {
@@ -106,7 +196,15 @@ impl Config {
         Self {
             load_paths: Vec::new(),
             load_path_pattern: Some("config/locales/*.*".into()),
+            catalogs: Vec::new(),
             default_locale: None,
+            plural_rules: HashMap::new(),
+            message_format: MessageFormat::default(),
+            interpolation: Interpolation::default(),
+            missing_var: None,
+            missing_var_hook: None,
+            pseudo_locales: Vec::new(),
+            strict: false,
         }
     }
 
@@ -128,15 +226,128 @@ impl Config {
         self
     }
 
+    /// Add the messages of a binary catalog written by `Dictionary::write_catalog`.
+    ///
+    /// The catalog is memory-mapped with the `mmap` feature (and must not be modified while in
+    /// use), or read into memory otherwise. Messages are parsed by `finish`, with the syntax they
+    /// were written with. Locales also loaded from translation files use the files instead, and
+    /// the catalog's default locale is used unless one is set.
+    ///
+    /// Examples:
+    /// ```rust,no_run
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_catalog("locales.bin").finish().unwrap();
+    /// ```
+    pub fn with_catalog<I: Into<PathBuf>>(mut self, path: I) -> Self {
+        self.catalogs.push(path.into());
+        self
+    }
+
     /// Set the default locale.
     pub fn with_default_locale<I: Into<String>>(mut self, default_locale: I) -> Self {
         self.default_locale = Some(default_locale.into());
         self
     }
 
+    /// Use a custom plural rule for `locale` (e.g. `tlh`, or `pt` to cover `pt-BR` too).
+    ///
+    /// The rule maps a `count` to the name of the catalog entry to use, replacing the built-in CLDR
+    /// rules for that locale. An explicit `zero` entry is still used for `0` when present.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let config = Config::default()
+    ///     .with_plural_rule("tlh", |ops| if ops.i == 1 && ops.v == 0 { "one" } else { "other" });
+    /// ```
+    pub fn with_plural_rule<I, F>(mut self, locale: I, rule: F) -> Self
+    where
+        I: Into<String>,
+        F: Fn(&PluralOperands) -> &'static str + Send + Sync + 'static,
+    {
+        self.plural_rules.insert(locale.into(), CustomRule(Arc::new(rule)));
+        self
+    }
+
+    /// Set the syntax of the messages in the translation files.
+    pub fn with_message_format(mut self, message_format: MessageFormat) -> Self {
+        self.message_format = message_format;
+        self
+    }
+
+    /// Set the placeholder syntax for messages: `{name}`, `%{name}` or `{{name}}`.
+    ///
+    /// Not used for ICU messages.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Set what to do when a message references a variable that was not passed.
+    ///
+    /// Can be overridden for a single call with `Opts::missing_var`. Without a policy, calls that
+    /// pass variables fail and calls that pass none keep the placeholders.
+    pub fn with_missing_var(mut self, missing_var: MissingVar) -> Self {
+        self.missing_var = Some(missing_var);
+        self
+    }
+
+    /// Call `hook` with `(locale, key, var)` whenever a missing variable is replaced (i.e. with any
+    /// `MissingVar` other than `Error`).
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let config = Config::default()
+    ///     .with_missing_var(MissingVar::Keep)
+    ///     .with_missing_var_hook(|locale, key, var| eprintln!("{}.{}: missing `{}`", locale, key, var));
+    /// ```
+    pub fn with_missing_var_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &str, &str) + Send + Sync + 'static,
+    {
+        self.missing_var_hook = Some(Warn(Arc::new(hook)));
+        self
+    }
+
+    /// Generate a pseudo-locale (e.g. `PseudoLocale::accented()` for `en-XA`) from the messages
+    /// of the default locale, loaded from a translation file or a catalog. The `date` and `time`
+    /// formats and names used by `Dictionary::localize` are kept as they are.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let config = Config::default()
+    ///     .with_pseudo_locale(PseudoLocale::accented().with_expansion(0.4))
+    ///     .with_pseudo_locale(PseudoLocale::bidi());
+    /// ```
+    pub fn with_pseudo_locale(mut self, pseudo_locale: PseudoLocale) -> Self {
+        self.pseudo_locales.push(pseudo_locale);
+        self
+    }
+
+    /// Check every message when the `Dictionary` is built, so `finish` fails on the first syntax
+    /// error instead of the message failing when it is used.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let result = Config::default()
+    ///     .with_localized_path("xx", "examples/locales/invalid/xx.yml")
+    ///     .with_strict(true)
+    ///     .finish();
+    /// assert!(result.is_err());
+    /// ```
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     /// Build the `Dictionary` item.
     pub fn finish(mut self) -> err::Result<Dictionary> {
         let mut out = Dictionary::default();
+        // The parsed files, only kept until they are indexed
+        let mut trees = HashMap::new();
 
         let glob_paths = match self.load_path_pattern {
             Some(load_path_pattern) => glob::glob(&load_path_pattern)
@@ -177,13 +388,53 @@ impl Config {
                 }
             };
 
-            out.inner.insert(locale, value);
+            trees.insert(locale, value);
+        }
+
+        for path in &self.catalogs {
+            let catalog = catalog::open(path)?;
+            out.default_locale = catalog.default_locale;
+            for (locale, messages) in catalog.locales {
+                out.index.insert(locale, Index::Catalog(messages));
+            }
         }
 
         if let Some(locale) = self.default_locale {
             out.default_locale = locale;
         }
 
+        out.plural_rules = self.plural_rules;
+        out.missing_var = self.missing_var;
+        out.missing_var_hook = self.missing_var_hook;
+
+        let syntax = match self.message_format {
+            MessageFormat::Strfmt => Syntax::Strfmt(self.interpolation),
+            MessageFormat::Icu => Syntax::Icu,
+        };
+        for (locale, value) in &trees {
+            out.index.insert(locale.clone(), Index::build(locale, value, syntax));
+        }
+
+        if !self.pseudo_locales.is_empty() {
+            let source = out.index.get(&out.default_locale).ok_or_else(|| {
+                err::Error::UnknownLocale(out.default_locale.clone().into_boxed_str())
+            })?;
+            let pseudo = self
+                .pseudo_locales
+                .iter()
+                .map(|pseudo| (pseudo.locale().to_string(), source.pseudo(pseudo)))
+                .collect::<Vec<_>>();
+            out.index.extend(pseudo);
+        }
+
+        if self.strict {
+            let mut locales = out.index.iter().collect::<Vec<_>>();
+            locales.sort_unstable_by_key(|(locale, _)| *locale);
+            for (_, index) in locales {
+                index.check()?;
+            }
+        }
+
         Ok(out)
     }
 }
@@ -0,0 +1,193 @@
+//! Currency formatting, using CLDR symbols and patterns and ISO 4217 minor units.
+
+use super::{
+    number::{self, Decimal},
+    plural::language,
+};
+
+/// How to show negative currency amounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CurrencyStyle {
+    /// "-$1.50" (the default)
+    #[default]
+    Standard,
+    /// "($1.50)" in locales that use parentheses for negative amounts in accounting
+    Accounting,
+}
+
+/// An amount of money in a currency, for the `{price, currency}` placeholder hint
+///
+/// Formatted for the locale of the call as a variable (with or without the hint); displays as
+/// the amount and the ISO 4217 code, e.g. `12.5 EUR`.
+///
+/// ```rust
+/// use loon::Money;
+/// assert_eq!(Money::new(12.5, "EUR").to_string(), "12.5 EUR");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Money {
+    pub(crate) amount: Decimal,
+    pub(crate) currency: String,
+}
+
+impl Money {
+    /// Create an amount of money in the currency with ISO 4217 `code` (e.g. `USD`).
+    pub fn new<N: Into<Decimal>, C: Into<String>>(amount: N, code: C) -> Self {
+        Self { amount: amount.into(), currency: code.into() }
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+/// `true` for three uppercase ASCII letters.
+pub(crate) fn is_code(s: &str) -> bool {
+    s.len() == 3 && s.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+/// ISO 4217 minor unit digits for `code`.
+fn minor_digits(code: &str) -> u32 {
+    match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// The symbol for `code` in `locale` (lowercase, `-` separated), falling back to the code.
+fn symbol<'c>(locale: &str, code: &'c str) -> &'c str {
+    let lang = language(locale);
+    let region = |regions: &[&str]| regions.iter().any(|r| locale.ends_with(r));
+
+    match (code, lang.as_str()) {
+        ("USD", "en") if region(&["-ca", "-au", "-nz", "-sg"]) => "US$",
+        ("USD", "fr") => "$US",
+        ("USD", _) => "$",
+        ("EUR", _) => "€",
+        ("GBP", _) => "£",
+        ("JPY", "ja") => "￥",
+        ("JPY", _) => "¥",
+        ("CNY", "zh") => "¥",
+        ("CNY", _) => "CN¥",
+        ("INR", _) => "₹",
+        ("KRW", _) => "₩",
+        ("ILS", _) => "₪",
+        ("VND", _) => "₫",
+        ("BRL", _) => "R$",
+        ("CAD", "en") | ("CAD", "fr") if region(&["-ca"]) => "$",
+        ("CAD", _) => "CA$",
+        ("AUD", "en") if region(&["-au"]) => "$",
+        ("AUD", _) => "A$",
+        ("MXN", "es") if region(&["-mx"]) => "$",
+        ("MXN", _) => "MX$",
+        ("RUB", "ru") => "₽",
+        ("UAH", "uk") => "₴",
+        ("PLN", "pl") => "zł",
+        ("CZK", "cs") => "Kč",
+        ("TRY", "tr") => "₺",
+        ("SEK", "sv") | ("NOK", "nb") | ("NOK", "nn") | ("NOK", "no") => "kr",
+        ("DKK", "da") => "kr.",
+        _ => code,
+    }
+}
+
+/// Placement of the currency symbol
+struct Pattern {
+    /// Symbol before the amount
+    prefix: bool,
+    /// Non-breaking space between the symbol and the amount
+    space: bool,
+    /// Parentheses for negative accounting amounts
+    parens: bool,
+}
+
+fn pattern(locale: &str) -> Pattern {
+    let suffix = Pattern { prefix: false, space: true, parens: false };
+
+    match language(locale).as_str() {
+        "de" if locale.starts_with("de-ch") || locale.starts_with("de-li") => {
+            Pattern { prefix: true, space: true, parens: false }
+        }
+        "nl" => Pattern { prefix: true, space: true, parens: true },
+        "pt" => Pattern { prefix: true, space: true, parens: false },
+        "fr" => Pattern { parens: true, ..suffix },
+        "de" | "es" | "it" | "pt-pt" | "ru" | "uk" | "be" | "pl" | "cs" | "sk" | "sv" | "fi"
+        | "nb" | "nn" | "no" | "da" | "el" | "ro" | "hu" | "bg" | "hr" | "lt" | "lv" | "et"
+        | "ar" | "fa" => suffix,
+        _ => Pattern { prefix: true, space: false, parens: true },
+    }
+}
+
+/// Format `amount` in the currency with ISO 4217 `code` for `locale`.
+pub(crate) fn format(locale: &str, amount: &Decimal, code: &str, style: CurrencyStyle) -> String {
+    let locale = locale.to_ascii_lowercase().replace('_', "-");
+    let symbols = number::symbols(&locale);
+    let pattern = pattern(&locale);
+    let symbol = symbol(&locale, code);
+
+    let amount = amount.with_scale(minor_digits(code));
+    let digits = number::digits(&amount, &symbols);
+
+    // CLDR spacing: keep letters in a symbol (e.g. `CHF`) apart from the digits.
+    let space = pattern.space
+        || (pattern.prefix && symbol.chars().last().map(char::is_alphabetic).unwrap_or(false));
+
+    let mut out = String::new();
+    let parens = amount.is_negative() && style == CurrencyStyle::Accounting && pattern.parens;
+    if parens {
+        out.push('(');
+    } else if amount.is_negative() {
+        out.push_str(symbols.minus);
+    }
+    if pattern.prefix {
+        out.push_str(symbol);
+        if space {
+            out.push('\u{a0}');
+        }
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits);
+        if space {
+            out.push('\u{a0}');
+        }
+        out.push_str(symbol);
+    }
+    if parens {
+        out.push(')');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{format, CurrencyStyle::*};
+    use crate::Decimal;
+
+    #[test]
+    fn formats() {
+        let price = Decimal::from(1234.5);
+
+        assert_eq!(format("en", &price, "USD", Standard), "$1,234.50");
+        assert_eq!(format("en-CA", &price, "USD", Standard), "US$1,234.50");
+        assert_eq!(format("en", &price, "EUR", Standard), "€1,234.50");
+        assert_eq!(format("de", &price, "EUR", Standard), "1.234,50\u{a0}€");
+        assert_eq!(format("fr", &price, "EUR", Standard), "1\u{202f}234,50\u{a0}€");
+        assert_eq!(format("nl", &price, "EUR", Standard), "€\u{a0}1.234,50");
+        assert_eq!(format("de-CH", &price, "CHF", Standard), "CHF\u{a0}1’234.50");
+        assert_eq!(format("en", &price, "CHF", Standard), "CHF\u{a0}1,234.50");
+        assert_eq!(format("ja", &price, "JPY", Standard), "￥1,234");
+        assert_eq!(format("en", &price, "KWD", Standard), "KWD\u{a0}1,234.500");
+        assert_eq!(format("en", &Decimal::from(5), "XYZ", Standard), "XYZ\u{a0}5.00");
+
+        let refund = Decimal::from(-3.456);
+        assert_eq!(format("en", &refund, "USD", Standard), "-$3.46");
+        assert_eq!(format("en", &refund, "USD", Accounting), "($3.46)");
+        assert_eq!(format("de", &refund, "EUR", Accounting), "-3,46\u{a0}€");
+        assert_eq!(format("fr", &refund, "EUR", Accounting), "(3,46\u{a0}€)");
+    }
+}
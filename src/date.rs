@@ -0,0 +1,304 @@
+//! Date and time localization, ruby-i18n style.
+//!
+//! Formats are `strftime` patterns (`%Y-%m-%d`, `%B %-d, %Y`), and month names, day names and
+//! AM/PM markers come from the `date` and `time` entries of the locale files.
+
+use super::err;
+
+/// A calendar date with an optional time of day, to be localized
+///
+/// Dates are formatted with the `date.formats` of the locale, and dates with a time with the
+/// `time.formats`. Can be built from a `std::time::SystemTime` (in UTC), or from `chrono` dates
+/// and times with the `chrono` feature.
+///
+/// ```rust
+/// use loon::DateTime;
+/// let date = DateTime::date(2020, 2, 29);
+/// assert_eq!(date.weekday(), 6);
+/// let time = date.and_time(13, 45, 0);
+/// assert_eq!(time.hour(), 13);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    time: Option<(u32, u32, u32)>,
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(if month <= 2 { year - 1 } else { year });
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let day_of_year = (153 * month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The proleptic Gregorian date for a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as i32, month, day)
+}
+
+impl DateTime {
+    /// Create a date (without a time of day).
+    ///
+    /// Panics if `month` or `day` is out of range.
+    pub fn date(year: i32, month: u32, day: u32) -> Self {
+        assert!((1..=12).contains(&month), "`month` must be between 1 and 12");
+        assert!(
+            (1..=days_in_month(year, month)).contains(&day),
+            "`day` must be between 1 and {}",
+            days_in_month(year, month)
+        );
+        Self { year, month, day, time: None }
+    }
+
+    /// Add a time of day.
+    ///
+    /// Panics if `hour`, `minute` or `second` is out of range.
+    pub fn and_time(self, hour: u32, minute: u32, second: u32) -> Self {
+        assert!(
+            hour < 24 && minute < 60 && second < 60,
+            "invalid time: {}:{}:{}",
+            hour,
+            minute,
+            second
+        );
+        Self { time: Some((hour, minute, second)), ..self }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// Month, from 1 (January) to 12.
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Day of the week, from 0 (Sunday) to 6, as indexed in `date.day_names`.
+    pub fn weekday(&self) -> u32 {
+        (self.days() + 4).rem_euclid(7) as u32
+    }
+
+    /// `true` if the value has a time of day.
+    pub fn has_time(&self) -> bool {
+        self.time.is_some()
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.time.map(|t| t.0).unwrap_or(0)
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.time.map(|t| t.1).unwrap_or(0)
+    }
+
+    pub fn second(&self) -> u32 {
+        self.time.map(|t| t.2).unwrap_or(0)
+    }
+
+    /// Days since 1970-01-01.
+    pub(crate) fn days(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day)
+    }
+
+    /// Day of the year, from 1.
+    fn ordinal(&self) -> i64 {
+        self.days() - days_from_civil(self.year, 1, 1) + 1
+    }
+}
+
+/// Uses the UTC date and time.
+impl From<std::time::SystemTime> for DateTime {
+    fn from(t: std::time::SystemTime) -> Self {
+        let secs = match t.duration_since(std::time::UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+        };
+        let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+        let secs = secs.rem_euclid(86_400) as u32;
+        Self { year, month, day, time: Some((secs / 3600, secs / 60 % 60, secs % 60)) }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for DateTime {
+    fn from(t: chrono::NaiveDate) -> Self {
+        use chrono::Datelike;
+        Self::date(t.year(), t.month(), t.day())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDateTime> for DateTime {
+    fn from(t: chrono::NaiveDateTime) -> Self {
+        use chrono::Timelike;
+        Self::from(t.date()).and_time(t.hour(), t.minute(), t.second().min(59))
+    }
+}
+
+/// Uses the local date and time of the time zone.
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for DateTime {
+    fn from(t: chrono::DateTime<Tz>) -> Self {
+        Self::from(t.naive_local())
+    }
+}
+
+/// Catalog name lookup: `(key, index) -> name`, e.g. `("date.month_names", 1) -> "January"`
+pub(crate) type NameFn<'a> = dyn Fn(&str, usize) -> err::Result<String> + 'a;
+
+/// Format `value` with the `strftime` style `pattern`, using `name` for localized names.
+///
+/// Supports `%Y %C %y %m %d %e %j %H %I %l %M %S %p %P %a %A %b %h %B %F %T %R %D %%`, and the
+/// `-` flag to remove padding (`%-d`).
+pub(crate) fn format(pattern: &str, value: &DateTime, name: &NameFn) -> err::Result<String> {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        let mut directive = chars.next();
+        let pad = directive != Some('-');
+        if !pad {
+            directive = chars.next();
+        }
+
+        let num = |n: i64, width: usize| {
+            if pad {
+                format!("{:0width$}", n, width = width)
+            } else {
+                n.to_string()
+            }
+        };
+        let hour12 = match value.hour() % 12 {
+            0 => 12,
+            h => h,
+        };
+
+        match directive {
+            Some('Y') => out.push_str(&value.year.to_string()),
+            Some('C') => out.push_str(&num(i64::from(value.year.div_euclid(100)), 2)),
+            Some('y') => out.push_str(&num(i64::from(value.year.rem_euclid(100)), 2)),
+            Some('m') => out.push_str(&num(i64::from(value.month), 2)),
+            Some('d') => out.push_str(&num(i64::from(value.day), 2)),
+            Some('e') => out.push_str(&format!("{:>2}", value.day)),
+            Some('j') => out.push_str(&num(value.ordinal(), 3)),
+            Some('H') => out.push_str(&num(i64::from(value.hour()), 2)),
+            Some('I') => out.push_str(&num(i64::from(hour12), 2)),
+            Some('l') => out.push_str(&format!("{:>2}", hour12)),
+            Some('M') => out.push_str(&num(i64::from(value.minute()), 2)),
+            Some('S') => out.push_str(&num(i64::from(value.second()), 2)),
+            Some('p') | Some('P') => {
+                let marker = name(if value.hour() < 12 { "time.am" } else { "time.pm" }, 0)?;
+                if directive == Some('P') {
+                    out.push_str(&marker.to_lowercase());
+                } else {
+                    out.push_str(&marker);
+                }
+            }
+            Some('a') => out.push_str(&name("date.abbr_day_names", value.weekday() as usize)?),
+            Some('A') => out.push_str(&name("date.day_names", value.weekday() as usize)?),
+            Some('b') | Some('h') => {
+                out.push_str(&name("date.abbr_month_names", value.month as usize)?)
+            }
+            Some('B') => out.push_str(&name("date.month_names", value.month as usize)?),
+            Some('F') => out.push_str(&format("%Y-%m-%d", value, name)?),
+            Some('T') => out.push_str(&format("%H:%M:%S", value, name)?),
+            Some('R') => out.push_str(&format("%H:%M", value, name)?),
+            Some('D') => out.push_str(&format("%m/%d/%y", value, name)?),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                return Err(err::custom(format!("Unknown date format directive: %{}", other)))
+            }
+            None => return Err(err::custom(format!("Incomplete date format: {}", pattern))),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{civil_from_days, days_from_civil, format, DateTime};
+    use crate::err;
+
+    fn english(key: &str, idx: usize) -> err::Result<String> {
+        let names: &[&str] = match key {
+            "date.month_names" => &["", "January", "February", "March"],
+            "date.abbr_month_names" => &["", "Jan", "Feb", "Mar"],
+            "date.day_names" => &["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday"],
+            "date.abbr_day_names" => &["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+            "time.am" => &["AM"],
+            "time.pm" => &["PM"],
+            _ => &[],
+        };
+        names.get(idx).map(|s| s.to_string()).ok_or_else(|| err::custom(key))
+    }
+
+    #[test]
+    fn converts() {
+        for days in &[-719_468, -1, 0, 1, 11_016, 18_321, 2_932_896] {
+            let (y, m, d) = civil_from_days(*days);
+            assert_eq!(days_from_civil(y, m, d), *days);
+        }
+        assert_eq!(civil_from_days(18_321), (2020, 2, 29));
+        assert_eq!(DateTime::date(1970, 1, 1).weekday(), 4);
+
+        let t = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_582_984_800);
+        assert_eq!(DateTime::from(t), DateTime::date(2020, 2, 29).and_time(14, 0, 0));
+    }
+
+    #[test]
+    fn formats() {
+        let date = DateTime::date(2020, 3, 4);
+        let time = date.and_time(9, 5, 7);
+
+        assert_eq!(format("%Y-%m-%d", &date, &english).unwrap(), "2020-03-04");
+        assert_eq!(format("%a, %-d %b %y", &date, &english).unwrap(), "Wed, 4 Mar 20");
+        assert_eq!(format("%A %B %e (%j)", &date, &english).unwrap(), "Wednesday March  4 (064)");
+        assert_eq!(
+            format("%F %T %p %P %%", &time, &english).unwrap(),
+            "2020-03-04 09:05:07 AM am %"
+        );
+        assert_eq!(format("%-I:%M %p", &time.and_time(0, 30, 0), &english).unwrap(), "12:30 AM");
+        assert!(format("%Q", &date, &english).is_err());
+        assert!(format("100%", &date, &english).is_err());
+    }
+}
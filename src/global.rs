@@ -0,0 +1,95 @@
+//! The global `Dictionary`, replaced atomically by `set_config`, `reload` and `set_dictionary`.
+//!
+//! Readers load the current dictionary from an `ArcSwapOption` without locking, and a replaced
+//! dictionary is dropped when the last call using it returns.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use arc_swap::ArcSwapOption;
+
+use super::{err, Config, Dictionary};
+
+/// A loaded dictionary, or the reason it failed to load
+type State = Result<Dictionary, Box<str>>;
+
+/// The configuration set with `set_config`, locked while loading or replacing the dictionary
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+/// The current dictionary, loaded on first use
+static CURRENT: ArcSwapOption<State> = ArcSwapOption::const_empty();
+
+// A panic while loading leaves the configuration as it was, so poisoning can be ignored.
+fn config() -> MutexGuard<'static, Option<Config>> {
+    CONFIG.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Build a dictionary from `config` (or the default `config/locales/*.*`).
+fn load(config: &Option<Config>) -> err::Result<Dictionary> {
+    config.clone().unwrap_or_else(Config::global).finish()
+}
+
+/// Load the dictionary on first use.
+///
+/// A failed load is stored too, so later calls report it rather than reading the files again
+/// (until `set_config` or `reload` succeeds).
+fn init() -> err::Result<Arc<State>> {
+    // Load under the lock, so racing threads don't each read the files.
+    let config = config();
+    if let Some(state) = CURRENT.load_full() {
+        return Ok(state);
+    }
+    match load(&config) {
+        Ok(dict) => {
+            let state = Arc::new(Ok(dict));
+            CURRENT.store(Some(Arc::clone(&state)));
+            Ok(state)
+        }
+        Err(e) => {
+            CURRENT.store(Some(Arc::new(Err(e.to_string().into_boxed_str()))));
+            Err(e)
+        }
+    }
+}
+
+/// Replace the current dictionary.
+///
+/// Takes the configuration lock, so a `reload` that started earlier can't overwrite `dict`.
+pub(crate) fn replace(dict: Dictionary) {
+    let _config = config();
+    CURRENT.store(Some(Arc::new(Ok(dict))));
+}
+
+/// Replace the configuration. The dictionary is loaded from it on first use.
+pub(crate) fn set_config(config: Config) {
+    let mut current = self::config();
+    *current = Some(config);
+    CURRENT.store(None);
+}
+
+/// Rebuild the dictionary from the current configuration.
+pub(crate) fn reload() -> err::Result<()> {
+    let config = config();
+    let dict = load(&config)?;
+    CURRENT.store(Some(Arc::new(Ok(dict))));
+    Ok(())
+}
+
+fn apply<T, F>(state: &State, f: F) -> err::Result<T>
+where
+    F: FnOnce(&Dictionary) -> err::Result<T>,
+{
+    match state {
+        Ok(dict) => f(dict),
+        Err(e) => Err(err::custom(format!("The global dictionary failed to load: {}", e))),
+    }
+}
+
+/// Call `f` with the current dictionary.
+pub(crate) fn with<T, F>(f: F) -> err::Result<T>
+where
+    F: FnOnce(&Dictionary) -> err::Result<T>,
+{
+    if let Some(state) = &*CURRENT.load() {
+        return apply(state, f);
+    }
+    apply(&*init()?, f)
+}
@@ -0,0 +1,132 @@
+//! HTML-safe translations.
+
+use std::fmt;
+
+/// Translated HTML that is safe to insert into a page without further escaping
+///
+/// Returned by `translate_html`: messages for keys ending in `_html` (or `.html`) are trusted
+/// markup with escaped variables, and all other messages are escaped in full.
+///
+/// ```rust
+/// use loon::SafeHtml;
+/// assert_eq!(SafeHtml::escape("<b>Tom & Jerry</b>").as_str(), "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SafeHtml(String);
+
+impl SafeHtml {
+    /// Escape plain `text`.
+    pub fn escape(text: &str) -> Self {
+        SafeHtml(escape(text))
+    }
+
+    /// Use trusted `html` as is.
+    pub(crate) fn trusted(html: String) -> Self {
+        SafeHtml(html)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::fmt::Display for SafeHtml {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for SafeHtml {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<SafeHtml> for String {
+    fn from(t: SafeHtml) -> Self {
+        t.0
+    }
+}
+
+/// What `translate_html` escapes while formatting a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escape {
+    /// Nothing: not formatting HTML
+    Nothing,
+    /// Variables and missing-variable replacements: the message is trusted `_html` markup
+    Values,
+    /// The message text as well: the message is plain text
+    All,
+}
+
+impl Escape {
+    /// Write the message `text` to `out`.
+    pub(crate) fn text(self, out: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+        match self {
+            Escape::All => write_escaped(out, text),
+            _ => out.write_str(text),
+        }
+    }
+
+    /// Write `value`, e.g. a missing-variable replacement, to `out`.
+    pub(crate) fn value(self, out: &mut dyn fmt::Write, value: &str) -> fmt::Result {
+        match self {
+            Escape::Nothing => out.write_str(value),
+            _ => write_escaped(out, value),
+        }
+    }
+}
+
+/// `true` if messages for `key` are trusted HTML (Rails' `_html` convention).
+pub(crate) fn is_html_key(key: &str) -> bool {
+    key.ends_with("_html") || key.ends_with(".html") || key == "html"
+}
+
+/// Write `text` to `out`, escaping `&`, `<`, `>`, `"` and `'`.
+pub(crate) fn write_escaped(out: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+    let mut start = 0;
+    for (idx, c) in text.char_indices() {
+        let entity = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            '\'' => "&#39;",
+            _ => continue,
+        };
+        out.write_str(&text[start..idx])?;
+        out.write_str(entity)?;
+        start = idx + 1;
+    }
+    out.write_str(&text[start..])
+}
+
+/// Escape `&`, `<`, `>`, `"` and `'`.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Writing to a `String` can't fail
+    let _ = write_escaped(&mut out, text);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{escape, is_html_key};
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom&#39;s&lt;/a&gt;"
+        );
+        assert!(is_html_key("terms_html"));
+        assert!(is_html_key("footer.html"));
+        assert!(!is_html_key("greeting"));
+        assert!(!is_html_key("xhtml"));
+    }
+}
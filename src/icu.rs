@@ -0,0 +1,563 @@
+//! ICU MessageFormat messages.
+//!
+//! Supports simple arguments (`{name}`, `{name, number}`), `plural` (with `offset:` and `=N`
+//! selectors), `selectordinal`, `select`, `#` inside plural branches (formatted as a number for the
+//! locale), and apostrophe quoting; other argument types are rejected when the message is parsed.
+//! Missing arguments follow the `MissingVar` policy, with `plural` and `select` falling back to
+//! their `other` branch in the lenient modes.
+//! `$t(key)` links in the message text are replaced with the linked translation.
+
+use std::fmt;
+
+use super::{
+    err,
+    interpolate::Missing,
+    link, number, rich,
+    value::{Hint, Vars},
+    Decimal, NumberStyle,
+};
+
+/// A parsed message
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Message(Vec<Part>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    /// `#` inside a plural branch
+    Hash,
+    /// `$t(key)`
+    Link(String),
+    /// `{name}` or `{name, kind[, style]}`; `number`, `currency`, `date` and `time` arguments are
+    /// formatted for the locale
+    Arg {
+        name: String,
+        hint: Option<Hint>,
+    },
+    Plural {
+        name: String,
+        ordinal: bool,
+        offset: i64,
+        branches: Vec<(Selector, Message)>,
+    },
+    Select {
+        name: String,
+        branches: Vec<(String, Message)>,
+    },
+}
+
+/// What `#` stands for inside a plural branch
+#[derive(Clone, Copy)]
+enum Hash<'h> {
+    /// The (offset) count, formatted for the locale
+    Count(&'h Decimal),
+    /// The replacement for a missing count
+    Missing(&'h str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Exact(Decimal),
+    Category(String),
+}
+
+/// Plural category lookup: `(count, ordinal) -> category`
+pub(crate) type PluralFn<'a> = dyn Fn(&Decimal, bool) -> &'static str + 'a;
+
+/// Linked translation lookup: writes the message for `key` to the output
+pub(crate) type LinkFn<'a> = dyn Fn(&mut dyn fmt::Write, &str) -> err::Result<()> + 'a;
+
+/// Everything needed to format a message
+pub(crate) struct Env<'a> {
+    pub(crate) vars: &'a Vars<'a>,
+    pub(crate) plural: &'a PluralFn<'a>,
+    pub(crate) link: &'a LinkFn<'a>,
+    pub(crate) missing: &'a Missing<'a>,
+    /// Add arguments as their own pieces, for `translate_rich`
+    pub(crate) rich: Option<&'a rich::Pieces>,
+}
+
+fn error<T: std::fmt::Display>(t: T) -> err::Error {
+    err::Error::MessageFormat(t.to_string().into_boxed_str())
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().map(char::is_whitespace).unwrap_or(false) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, c: char) -> err::Result<()> {
+        match self.bump() {
+            Some(found) if found == c => Ok(()),
+            Some(found) => {
+                Err(error(format!("expected `{}`, found `{}` at {}", c, found, self.pos)))
+            }
+            None => Err(error(format!("expected `{}`, found end of message", c))),
+        }
+    }
+
+    fn word(&mut self) -> &'s str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == ',' || c == '{' || c == '}' {
+                break;
+            }
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn message(&mut self, in_plural: bool, nested: bool) -> err::Result<Message> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            if let Some((key, len)) = link::reference(&self.src[self.pos..]) {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Link(key.to_string()));
+                self.pos += len;
+                continue;
+            }
+
+            match self.peek() {
+                None if nested => return Err(error("unterminated branch")),
+                None => break,
+                Some('}') if nested => break,
+                Some('}') => return Err(error(format!("unmatched `}}` at {}", self.pos))),
+                Some('{') => {
+                    self.bump();
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(self.argument(in_plural)?);
+                }
+                Some('#') if in_plural => {
+                    self.bump();
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Hash);
+                }
+                Some('\'') => {
+                    self.bump();
+                    match self.peek() {
+                        Some('\'') => {
+                            self.bump();
+                            text.push('\'');
+                        }
+                        Some('{') | Some('}') | Some('|') => self.quoted(&mut text),
+                        Some('#') if in_plural => self.quoted(&mut text),
+                        _ => text.push('\''),
+                    }
+                }
+                Some(c) => {
+                    self.bump();
+                    text.push(c);
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Message(parts))
+    }
+
+    /// Read quoted literal text, after the opening apostrophe.
+    fn quoted(&mut self, text: &mut String) {
+        while let Some(c) = self.bump() {
+            if c == '\'' {
+                if self.peek() == Some('\'') {
+                    self.bump();
+                } else {
+                    return;
+                }
+            }
+            text.push(c);
+        }
+    }
+
+    fn argument(&mut self, in_plural: bool) -> err::Result<Part> {
+        self.skip_ws();
+        let name = self.word().to_string();
+        if name.is_empty() {
+            return Err(error(format!("missing argument name at {}", self.pos)));
+        }
+        self.skip_ws();
+
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(Part::Arg { name, hint: None });
+        }
+
+        self.expect(',')?;
+        self.skip_ws();
+        let kind = self.word().to_string();
+        self.skip_ws();
+
+        match kind.as_str() {
+            "plural" | "selectordinal" => {
+                self.expect(',')?;
+                self.plural(name, kind == "selectordinal")
+            }
+            "select" => {
+                self.expect(',')?;
+                self.select(name, in_plural)
+            }
+            "" => Err(error(format!("missing argument type at {}", self.pos))),
+            _ => {
+                let style = if self.peek() == Some(',') {
+                    self.bump();
+                    Some(self.style()?)
+                } else {
+                    None
+                };
+                let hint = match Hint::parse(&kind, style) {
+                    Some(hint) => hint,
+                    None if kind == "number" || kind == "currency" => {
+                        return Err(error(format!("unsupported {} style at {}", kind, self.pos)))
+                    }
+                    None => {
+                        return Err(error(format!(
+                            "unknown argument type `{}` at {}",
+                            kind, self.pos
+                        )))
+                    }
+                };
+                self.expect('}')?;
+                Ok(Part::Arg { name, hint: Some(hint) })
+            }
+        }
+    }
+
+    /// Read an argument style up to (not including) the closing brace.
+    fn style(&mut self) -> err::Result<&'s str> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => return Err(error("unterminated argument")),
+                Some('{') => depth += 1,
+                Some('}') if depth == 0 => break,
+                Some('}') => depth -= 1,
+                _ => {}
+            }
+            self.bump();
+        }
+        Ok(self.src[start..self.pos].trim())
+    }
+
+    fn branches(&mut self, in_plural: bool) -> err::Result<Vec<(String, Message)>> {
+        let mut out = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.peek() == Some('}') {
+                self.bump();
+                break;
+            }
+            let selector = self.word().to_string();
+            if selector.is_empty() {
+                return Err(error(format!("missing selector at {}", self.pos)));
+            }
+            self.skip_ws();
+            self.expect('{')?;
+            let message = self.message(in_plural, true)?;
+            self.expect('}')?;
+            out.push((selector, message));
+        }
+        if !out.iter().any(|(selector, _)| selector == "other") {
+            return Err(error("missing `other` branch"));
+        }
+        Ok(out)
+    }
+
+    fn plural(&mut self, name: String, ordinal: bool) -> err::Result<Part> {
+        self.skip_ws();
+        let mut offset = 0;
+        if self.src[self.pos..].starts_with("offset:") {
+            self.pos += "offset:".len();
+            self.skip_ws();
+            let word = self.word();
+            offset = word.parse().map_err(|_| error(format!("invalid offset: {}", word)))?;
+        }
+
+        let branches = self
+            .branches(true)?
+            .into_iter()
+            .map(|(selector, message)| {
+                let selector = match selector.strip_prefix('=') {
+                    Some(exact) => Selector::Exact(exact.parse()?),
+                    None => Selector::Category(selector),
+                };
+                Ok((selector, message))
+            })
+            .collect::<err::Result<Vec<_>>>()?;
+
+        Ok(Part::Plural { name, ordinal, offset, branches })
+    }
+
+    fn select(&mut self, name: String, in_plural: bool) -> err::Result<Part> {
+        Ok(Part::Select { name, branches: self.branches(in_plural)? })
+    }
+}
+
+impl std::str::FromStr for Message {
+    type Err = err::Error;
+
+    fn from_str(s: &str) -> err::Result<Self> {
+        Parser { src: s, pos: 0 }.message(false, false)
+    }
+}
+
+impl Message {
+    /// Format the message to `out` with the variables, plural rules and links in `env`.
+    pub(crate) fn format(&self, out: &mut dyn fmt::Write, env: &Env) -> err::Result<()> {
+        self.write(out, env, None)
+    }
+
+    /// The message text, if it has no arguments or links.
+    pub(crate) fn plain(&self) -> Option<&str> {
+        match &self.0[..] {
+            [] => Some(""),
+            [Part::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    fn write(&self, out: &mut dyn fmt::Write, env: &Env, hash: Option<Hash>) -> err::Result<()> {
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => env.vars.escape.text(out, text)?,
+                Part::Hash => match hash {
+                    Some(Hash::Count(count)) => out.write_str(&number::format(
+                        env.vars.locale,
+                        count,
+                        NumberStyle::Decimal,
+                    ))?,
+                    Some(Hash::Missing(replacement)) => env.vars.escape.value(out, replacement)?,
+                    None => out.write_char('#')?,
+                },
+                Part::Link(key) => (env.link)(out, key)?,
+                Part::Arg { name, hint } => match env.vars.get(name) {
+                    Some(value) => {
+                        let write = |out: &mut dyn fmt::Write| {
+                            let value =
+                                env.vars.format(value, hint.as_ref())?.ok_or_else(|| {
+                                    error(format!("argument does not fit its format: {}", name))
+                                })?;
+                            Ok(out.write_str(&value)?)
+                        };
+                        match env.rich {
+                            Some(rich) => rich.var(name, write)?,
+                            None => write(out)?,
+                        }
+                    }
+                    None => env.vars.escape.value(out, &missing(env, name)?)?,
+                },
+                Part::Plural { name, ordinal, offset, branches } => {
+                    let by_category = |category: &str| {
+                        branches.iter().find(|(selector, _)| match selector {
+                            Selector::Category(c) => c == category,
+                            _ => false,
+                        })
+                    };
+
+                    let value = match env.vars.get(name) {
+                        Some(value) => value
+                            .as_decimal()
+                            .ok_or_else(|| error(format!("argument is not a number: {}", name)))?,
+                        None => {
+                            // Lenient policies use the `other` branch, with `#` replaced.
+                            let replacement = missing(env, name)?;
+                            if let Some((_, message)) = by_category("other") {
+                                message.write(out, env, Some(Hash::Missing(&replacement)))?;
+                            }
+                            continue;
+                        }
+                    };
+                    let shifted = value.sub_integer(*offset);
+                    let category = (env.plural)(&shifted, *ordinal);
+
+                    let exact = branches.iter().find(|(selector, _)| match selector {
+                        Selector::Exact(exact) => exact.to_f64() == value.to_f64(),
+                        _ => false,
+                    });
+
+                    if let Some((_, message)) =
+                        exact.or_else(|| by_category(category)).or_else(|| by_category("other"))
+                    {
+                        message.write(out, env, Some(Hash::Count(&shifted)))?;
+                    }
+                }
+                Part::Select { name, branches } => {
+                    let value = match env.vars.get(name) {
+                        Some(value) => value.to_string(),
+                        None => {
+                            // Lenient policies use the `other` branch.
+                            missing(env, name)?;
+                            String::from("other")
+                        }
+                    };
+                    if let Some((_, message)) = branches
+                        .iter()
+                        .find(|(selector, _)| *selector == value)
+                        .or_else(|| branches.iter().find(|(selector, _)| selector == "other"))
+                    {
+                        message.write(out, env, hash)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the replacement for the missing argument `name` with the configured `MissingVar` policy.
+fn missing(env: &Env, name: &str) -> err::Result<String> {
+    let placeholder = format!("{{{}}}", name);
+    env.missing
+        .replace(name, &placeholder)
+        .map(String::from)
+        .ok_or_else(|| error(format!("missing argument: {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Env, Message};
+    use crate::{
+        err,
+        html::Escape,
+        interpolate::{Missing, MissingVar},
+        value::{Value, VarMap, Vars},
+        DateTime, Decimal,
+    };
+
+    fn format_with(
+        locale: &str,
+        policy: &MissingVar,
+        msg: &str,
+        vars: &[(&str, &str)],
+    ) -> err::Result<String> {
+        let mut values = VarMap::default();
+        for (k, v) in vars {
+            values.insert(k.to_string(), Value::from(*v));
+        }
+        let localize = |value: &DateTime, format: &str| -> err::Result<String> {
+            Ok(format!("{} ({})", Value::from(*value), format))
+        };
+        let translate = |key: &str| -> err::Result<String> { Ok(key.to_uppercase()) };
+        let vars = Vars {
+            locale,
+            values: &values,
+            localize: &localize,
+            translate: &translate,
+            escape: Escape::Nothing,
+        };
+        let plural = |count: &Decimal, ordinal: bool| {
+            let ops = count.into();
+            if ordinal {
+                crate::plural::ordinal(locale, &ops).as_str()
+            } else {
+                crate::plural::cardinal(locale, &ops).as_str()
+            }
+        };
+        let link = |out: &mut dyn std::fmt::Write, key: &str| -> err::Result<()> {
+            Ok(out.write_str(&key.to_uppercase())?)
+        };
+        let missing = Missing { policy, warn: None, locale, key: &"key" };
+        let env = Env { vars: &vars, plural: &plural, link: &link, missing: &missing, rich: None };
+        let mut out = String::new();
+        msg.parse::<Message>()?.format(&mut out, &env)?;
+        Ok(out)
+    }
+
+    fn format(msg: &str, vars: &[(&str, &str)]) -> String {
+        format_with("en", &MissingVar::Keep, msg, vars).unwrap()
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(format("Hello, {name}!", &[("name", "Jacob")]), "Hello, Jacob!");
+        assert_eq!(format("{n, number} items", &[("n", "5")]), "5 items");
+        assert_eq!(
+            format("{n, number} / {r, number, percent}", &[("n", "1234.5"), ("r", "0.5")]),
+            "1,234.5 / 50%"
+        );
+
+        let msg = "{count, plural, =0 {no items} one {# item} other {# items}}";
+        assert_eq!(format(msg, &[("count", "0")]), "no items");
+        assert_eq!(format(msg, &[("count", "1")]), "1 item");
+        assert_eq!(format(msg, &[("count", "1.5")]), "1.5 items");
+
+        let msg = "{n, plural, offset:1 =0 {nobody} =1 {{host}} one {{host} and # other} \
+                   other {{host} and # others}}";
+        assert_eq!(format(msg, &[("n", "1"), ("host", "Sam")]), "Sam");
+        assert_eq!(format(msg, &[("n", "2"), ("host", "Sam")]), "Sam and 1 other");
+        assert_eq!(format(msg, &[("n", "5"), ("host", "Sam")]), "Sam and 4 others");
+
+        let msg = "{place, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}";
+        assert_eq!(format(msg, &[("place", "22")]), "22nd");
+
+        let msg = "{gender, select, female {She} male {He} other {They}} invited \
+                   {n, plural, one {a friend} other {# friends}}.";
+        assert_eq!(format(msg, &[("gender", "female"), ("n", "1")]), "She invited a friend.");
+        assert_eq!(format(msg, &[("gender", "x"), ("n", "3")]), "They invited 3 friends.");
+
+        assert_eq!(format("It''s '{quoted}' #", &[]), "It's {quoted} #");
+        assert_eq!(format("{n, plural, other {'#' is #}}", &[("n", "2")]), "# is 2");
+        assert_eq!(format("Welcome to $t(app.name)!", &[]), "Welcome to APP.NAME!");
+        assert_eq!(format("$t(not a key) $t(", &[]), "$t(not a key) $t(");
+        assert_eq!(format("Hello, {name}!", &[]), "Hello, {name}!");
+
+        let msg = "{n, plural, one {# item} other {# items}}";
+        assert_eq!(format(msg, &[("n", "1234")]), "1,234 items");
+        let de = format_with("de", &MissingVar::Keep, msg, &[("n", "1234")]).unwrap();
+        assert_eq!(de, "1.234 items");
+    }
+
+    #[test]
+    fn missing_arguments() {
+        let msg = "{gender, select, female {She} other {They}} sent \
+                   {n, plural, one {# message} other {# messages}}.";
+        assert_eq!(format(msg, &[]), "They sent {n} messages.");
+        let marker = MissingVar::Marker("?".into());
+        assert_eq!(format_with("en", &marker, msg, &[]).unwrap(), "They sent ? messages.");
+        assert!(format_with("en", &MissingVar::Error, msg, &[("gender", "female")]).is_err());
+        assert!(format_with("en", &MissingVar::Error, msg, &[("n", "2")]).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert!("{name".parse::<Message>().is_err());
+        assert!("name}".parse::<Message>().is_err());
+        assert!("{n, plural, one {x}}".parse::<Message>().is_err());
+        assert!("{n, select, a {x} other {y}".parse::<Message>().is_err());
+        assert!("{n, plural, =x {x} other {y}}".parse::<Message>().is_err());
+        assert!("{n, number, fancy}".parse::<Message>().is_err());
+        assert!("{n, currency, euros}".parse::<Message>().is_err());
+        assert!("{x, foo}".parse::<Message>().is_err());
+        assert!("{x, foo, bar}".parse::<Message>().is_err());
+    }
+}
@@ -0,0 +1,355 @@
+//! Flattened message index: the messages of a locale by dot-delimited key path, built by
+//! `Config::finish` (or read from a binary catalog) so lookups don't walk the catalog tree.
+
+use std::collections::HashMap;
+
+use super::{
+    catalog, err, icu,
+    interpolate::{self, Interpolation},
+    pseudo::PseudoLocale,
+};
+
+/// Message syntax of a locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Syntax {
+    Strfmt(Interpolation),
+    Icu,
+}
+
+impl Syntax {
+    /// Parse the message `text` at `path` of `locale` (which are named in errors).
+    pub(crate) fn compile(self, locale: &str, path: &str, text: &str) -> Parsed {
+        match self {
+            Syntax::Strfmt(interpolation) => {
+                interpolate::Template::compile(text, interpolation).map(Compiled::Template)
+            }
+            Syntax::Icu => text.parse().map(Compiled::Icu),
+        }
+        .map_err(|e| format!("{} ({}.{})", e, locale, path).into_boxed_str())
+    }
+}
+
+/// A parsed message, or its syntax error (reported when the message is used)
+pub(crate) type Parsed = Result<Compiled, Box<str>>;
+
+/// A parsed message
+#[derive(Debug)]
+pub(crate) enum Compiled {
+    Template(interpolate::Template),
+    Icu(icu::Message),
+}
+
+impl Compiled {
+    /// The message text, if it needs no formatting.
+    pub(crate) fn plain(&self) -> Option<&str> {
+        match self {
+            Compiled::Template(template) => template.plain(),
+            Compiled::Icu(message) => message.plain(),
+        }
+    }
+}
+
+/// A message in the index
+#[derive(Clone, Copy)]
+pub(crate) struct Message<'i> {
+    /// The message as written
+    pub(crate) text: &'i str,
+    pub(crate) parsed: &'i Parsed,
+}
+
+impl<'i> Message<'i> {
+    /// The parsed message, or its syntax error.
+    pub(crate) fn compiled(&self) -> err::Result<&'i Compiled> {
+        self.parsed.as_ref().map_err(|e| err::Error::MessageFormat(e.clone()))
+    }
+}
+
+/// The child names of a map or list of messages (for `select` values and plural categories)
+#[derive(Clone, Copy)]
+pub(crate) enum Branch<'i> {
+    /// Sorted names
+    Names(&'i [Box<str>]),
+    /// `\0`-separated names, from a catalog
+    Joined(&'i str),
+}
+
+impl Branch<'_> {
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        match self {
+            Branch::Names(names) => names.binary_search_by(|child| (**child).cmp(name)).is_ok(),
+            Branch::Joined(names) => names.split('\0').any(|child| child == name),
+        }
+    }
+
+    pub(crate) fn names(&self) -> Vec<&str> {
+        match self {
+            Branch::Names(names) => names.iter().map(|name| &**name).collect(),
+            Branch::Joined("") => Vec::new(),
+            Branch::Joined(names) => names.split('\0').collect(),
+        }
+    }
+
+    /// The names, `\0`-separated.
+    pub(crate) fn joined(&self) -> String {
+        match self {
+            Branch::Names(names) => names.join("\0"),
+            Branch::Joined(names) => String::from(*names),
+        }
+    }
+}
+
+/// A message, a map or list of messages, or another value
+#[derive(Clone, Copy)]
+pub(crate) enum Node<'i> {
+    Message(Message<'i>),
+    Branch(Branch<'i>),
+    /// A list, with children named by their position
+    List(Branch<'i>),
+    /// A number, boolean or null, as JSON
+    Value(&'i str),
+}
+
+#[derive(Debug)]
+pub(crate) enum Entry {
+    Message { text: Box<str>, parsed: Parsed },
+    Branch { names: Box<[Box<str>]>, list: bool },
+    Value(Box<str>),
+}
+
+/// The messages of a locale
+#[derive(Debug)]
+pub(crate) enum Index {
+    /// Built from a translation file, with every message parsed
+    Owned { syntax: Syntax, entries: HashMap<Box<str>, Entry> },
+    /// Read from a binary catalog
+    Catalog(catalog::Locale),
+}
+
+impl Index {
+    /// Flatten the catalog `value` of `locale`, parsing each message with `syntax`.
+    pub(crate) fn build(locale: &str, value: &serde_json::Value, syntax: Syntax) -> Self {
+        let mut entries = HashMap::new();
+        walk(&mut entries, &mut String::new(), value, &|path: &str, text: &str| {
+            syntax.compile(locale, path, text)
+        });
+        Index::Owned { syntax, entries }
+    }
+
+    /// Pseudo-localize every message, for the locale of `pseudo`.
+    ///
+    /// The `date` and `time` namespaces are formats and names for `localize`, so they are copied.
+    pub(crate) fn pseudo(&self, pseudo: &PseudoLocale) -> Self {
+        let syntax = self.syntax();
+        let interpolation = match syntax {
+            Syntax::Strfmt(interpolation) => Some(interpolation),
+            Syntax::Icu => None,
+        };
+        let mut entries = HashMap::new();
+        for (path, node) in self.entries() {
+            let entry = match node {
+                Node::Message(message) if is_date_data(path) => {
+                    let parsed = syntax.compile(pseudo.locale(), path, message.text);
+                    Entry::Message { text: message.text.into(), parsed }
+                }
+                Node::Message(message) => {
+                    let text = pseudo.message(message.text, interpolation);
+                    let parsed = syntax.compile(pseudo.locale(), path, &text);
+                    Entry::Message { text: text.into_boxed_str(), parsed }
+                }
+                Node::Branch(branch) | Node::List(branch) => Entry::Branch {
+                    names: branch.names().into_iter().map(Box::from).collect(),
+                    list: matches!(node, Node::List(_)),
+                },
+                Node::Value(value) => Entry::Value(value.into()),
+            };
+            entries.insert(Box::from(path), entry);
+        }
+        Index::Owned { syntax, entries }
+    }
+
+    /// Check that every message parses.
+    pub(crate) fn check(&self) -> err::Result<()> {
+        for (_, node) in self.entries() {
+            if let Node::Message(message) = node {
+                message.compiled()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn syntax(&self) -> Syntax {
+        match self {
+            Index::Owned { syntax, .. } => *syntax,
+            Index::Catalog(locale) => locale.syntax(),
+        }
+    }
+
+    /// Get the entry at `path`.
+    pub(crate) fn get(&self, path: &str) -> Option<Node<'_>> {
+        match self {
+            Index::Owned { entries, .. } => entries.get(path).map(|entry| match entry {
+                Entry::Message { text, parsed } => Node::Message(Message { text, parsed }),
+                Entry::Branch { names, list: false } => Node::Branch(Branch::Names(names)),
+                Entry::Branch { names, list: true } => Node::List(Branch::Names(names)),
+                Entry::Value(value) => Node::Value(value),
+            }),
+            Index::Catalog(locale) => locale.get(path),
+        }
+    }
+
+    /// Get the message at `path`.
+    pub(crate) fn message(&self, path: &str) -> Option<Message<'_>> {
+        match self.get(path) {
+            Some(Node::Message(message)) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Get the map or list of messages at `path`.
+    pub(crate) fn branch(&self, path: &str) -> Option<Branch<'_>> {
+        match self.get(path) {
+            Some(Node::Branch(branch)) | Some(Node::List(branch)) => Some(branch),
+            _ => None,
+        }
+    }
+
+    /// Rebuild the value at `path` as it was written.
+    pub(crate) fn tree(&self, path: &str) -> Option<serde_json::Value> {
+        let child = |name: &str| self.tree(&format!("{}.{}", path, name));
+        match self.get(path)? {
+            Node::Message(message) => Some(serde_json::Value::String(message.text.into())),
+            Node::Branch(branch) => Some(
+                branch
+                    .names()
+                    .into_iter()
+                    .filter_map(|name| Some((name.to_string(), child(name)?)))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into(),
+            ),
+            Node::List(branch) => {
+                let len = branch.names().len();
+                Some((0..len).filter_map(|i| child(&i.to_string())).collect())
+            }
+            Node::Value(value) => serde_json::from_str(value).ok(),
+        }
+    }
+
+    /// Get all entries, sorted by path.
+    pub(crate) fn entries(&self) -> Vec<(&str, Node<'_>)> {
+        match self {
+            Index::Owned { entries, .. } => {
+                let mut out = entries
+                    .keys()
+                    .filter_map(|path| self.get(path).map(|node| (&**path, node)))
+                    .collect::<Vec<_>>();
+                out.sort_unstable_by_key(|(path, _)| *path);
+                out
+            }
+            Index::Catalog(locale) => locale.entries(),
+        }
+    }
+}
+
+/// Whether `path` is in the `date` or `time` namespace.
+fn is_date_data(path: &str) -> bool {
+    let namespace = path.split('.').next().unwrap_or_default();
+    namespace == "date" || namespace == "time"
+}
+
+type CompileFn<'a> = dyn Fn(&str, &str) -> Parsed + 'a;
+
+/// Add the entries for `value` at `path`.
+fn walk(
+    entries: &mut HashMap<Box<str>, Entry>,
+    path: &mut String,
+    value: &serde_json::Value,
+    compile: &CompileFn,
+) {
+    let mut names = Vec::new();
+    let mut child = |part: &str, value: &serde_json::Value| {
+        let len = path.len();
+        if len > 0 {
+            path.push('.');
+        }
+        path.push_str(part);
+        walk(entries, path, value, compile);
+        path.truncate(len);
+        names.push(Box::from(part));
+    };
+
+    let entry = match value {
+        serde_json::Value::Object(map) => {
+            for (part, value) in map {
+                child(part, value);
+            }
+            names.sort_unstable();
+            Entry::Branch { names: names.into_boxed_slice(), list: false }
+        }
+        serde_json::Value::Array(arr) => {
+            for (i, value) in arr.iter().enumerate() {
+                child(&i.to_string(), value);
+            }
+            names.sort_unstable();
+            Entry::Branch { names: names.into_boxed_slice(), list: true }
+        }
+        serde_json::Value::String(text) => {
+            Entry::Message { text: text.as_str().into(), parsed: compile(path, text) }
+        }
+        value => Entry::Value(value.to_string().into_boxed_str()),
+    };
+    if !path.is_empty() {
+        entries.insert(path.as_str().into(), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Index, Syntax};
+    use crate::interpolate::Interpolation;
+
+    #[test]
+    fn flattens() {
+        let value = serde_json::json!({
+            "greeting": "Hello",
+            "messages": { "one": "One message", "other": "{count} messages" },
+            "days": ["Mon", "Tue"],
+            "months": [null, "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct"],
+            "count": 3,
+            "empty": {},
+        });
+        let syntax = Syntax::Strfmt(Interpolation::Brace);
+        let index = Index::build("en", &value, syntax);
+
+        assert_eq!(index.message("greeting").unwrap().text, "Hello");
+        assert_eq!(index.message("messages.other").unwrap().text, "{count} messages");
+        assert_eq!(index.message("days.1").unwrap().text, "Tue");
+        assert!(index.message("messages").is_none());
+        assert!(index.message("count").is_none());
+
+        let branch = index.branch("messages").unwrap();
+        assert!(branch.contains("one") && branch.contains("other") && !branch.contains("few"));
+        assert!(index.branch("greeting").is_none());
+
+        let paths = index.entries().into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(
+            paths[..8],
+            ["count", "days", "days.0", "days.1", "empty", "greeting", "messages", "messages.one"]
+        );
+        assert!(index.get("count").is_some() && index.get("months.0").is_some());
+
+        // The values are rebuilt as written
+        for path in ["days", "months", "count", "empty", "messages"] {
+            assert_eq!(index.tree(path).as_ref(), value.get(path));
+        }
+        assert_eq!(index.tree("messages.one"), Some(serde_json::json!("One message")));
+
+        // Syntax errors are kept for the message that has them
+        let index =
+            Index::build("en", &serde_json::json!({ "a": { "b": "{x" }, "c": "ok" }), syntax);
+        let err = index.message("a.b").unwrap().compiled().unwrap_err();
+        assert!(err.to_string().contains("en.a.b"));
+        assert!(index.message("c").unwrap().compiled().is_ok());
+        assert!(index.check().is_err());
+    }
+}
@@ -0,0 +1,498 @@
+//! Placeholder interpolation for (non-ICU) messages.
+
+use std::{fmt, sync::Arc};
+
+use super::{
+    err,
+    icu::LinkFn,
+    link, rich,
+    value::{Hint, Value, Vars},
+};
+
+/// Placeholder syntax for messages (also a helper for setting `interpolation` configuration)
+///
+/// Only used with `MessageFormat::Strfmt`. Escapes are only unescaped in messages with
+/// placeholders: a message without any is used as written, like ruby-i18n does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// `{name}`, with optional `strfmt` format specs (`{name:>10}`) or number hints
+    /// (`{name, number}`); `{{` and `}}` for literal braces (the default)
+    #[default]
+    Brace,
+    /// ruby-i18n style `%{name}`; `%%` for a literal `%`
+    Percent,
+    /// `{{name}}`; `\{{` for literal double braces
+    DoubleBrace,
+}
+
+/// What to do when a message references a variable that was not passed (also a helper for
+/// setting `missing_var` configuration or option)
+///
+/// If no policy is set, calls that pass variables use `Error` and calls that pass none use `Keep`
+/// (returning the message with its placeholders, as before policies were configurable).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingVar {
+    /// Fail the `translate` call
+    Error,
+    /// Leave the placeholder visible, e.g. `Hello, {name}!`
+    Keep,
+    /// Substitute an empty string
+    Empty,
+    /// Substitute the given marker, e.g. `[missing]`
+    Marker(String),
+}
+
+/// Hook called with `(locale, key, var)` when a missing variable is replaced
+pub(crate) type WarnFn = dyn Fn(&str, &str, &str) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct Warn(pub(crate) Arc<WarnFn>);
+
+impl std::fmt::Debug for Warn {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Warn")
+    }
+}
+
+/// How missing variables are handled while formatting the message for `key`
+pub(crate) struct Missing<'a> {
+    pub(crate) policy: &'a MissingVar,
+    pub(crate) warn: Option<&'a Warn>,
+    pub(crate) locale: &'a str,
+    pub(crate) key: &'a dyn std::fmt::Display,
+}
+
+impl Missing<'_> {
+    /// Get the replacement for the missing variable `name` (written as `placeholder` in the
+    /// message), or `None` if formatting should fail.
+    pub(crate) fn replace<'s>(&'s self, name: &str, placeholder: &'s str) -> Option<&'s str> {
+        let out = match self.policy {
+            MissingVar::Error => return None,
+            MissingVar::Keep => placeholder,
+            MissingVar::Empty => "",
+            MissingVar::Marker(marker) => marker,
+        };
+        if let Some(warn) = self.warn {
+            (warn.0)(self.locale, &self.key.to_string(), name);
+        }
+        Some(out)
+    }
+}
+
+/// A piece of a message
+#[derive(Debug, PartialEq)]
+pub(crate) enum Segment<'s> {
+    Text(&'s str),
+    /// The contents of a placeholder, e.g. `name`, `name:>10` or `name, number`
+    Placeholder(&'s str),
+}
+
+fn unmatched(c: &str, text: &str) -> err::Error {
+    err::Error::Strfmt(strfmt::FmtError::Invalid(format!("unmatched `{}` in: {}", c, text)))
+}
+
+/// Split `text` into literal text and placeholders.
+pub(crate) fn parse(text: &str, syntax: Interpolation) -> err::Result<Vec<Segment<'_>>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+
+    macro_rules! flush {
+        () => {
+            if idx > start {
+                out.push(Segment::Text(&text[start..idx]));
+            }
+        };
+    }
+
+    while idx < text.len() {
+        let rest = &text[idx..];
+        match syntax {
+            Interpolation::Brace => {
+                if rest.starts_with("{{") || rest.starts_with("}}") {
+                    flush!();
+                    out.push(Segment::Text(&rest[..1]));
+                    idx += 2;
+                    start = idx;
+                } else if rest.starts_with('{') {
+                    let end = rest.find('}').ok_or_else(|| unmatched("{", text))?;
+                    flush!();
+                    out.push(Segment::Placeholder(&rest[1..end]));
+                    idx += end + 1;
+                    start = idx;
+                } else if rest.starts_with('}') {
+                    return Err(unmatched("}", text));
+                } else {
+                    idx += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                }
+            }
+            Interpolation::Percent => {
+                if rest.starts_with("%%") {
+                    flush!();
+                    out.push(Segment::Text(&rest[..1]));
+                    idx += 2;
+                    start = idx;
+                } else if rest.starts_with("%{") {
+                    let end = rest.find('}').ok_or_else(|| unmatched("%{", text))?;
+                    flush!();
+                    out.push(Segment::Placeholder(rest[2..end].trim()));
+                    idx += end + 1;
+                    start = idx;
+                } else {
+                    idx += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                }
+            }
+            Interpolation::DoubleBrace => {
+                if rest.starts_with("\\{{") {
+                    flush!();
+                    out.push(Segment::Text(&rest[1..3]));
+                    idx += 3;
+                    start = idx;
+                } else if rest.starts_with("{{") {
+                    let end = rest.find("}}").ok_or_else(|| unmatched("{{", text))?;
+                    flush!();
+                    out.push(Segment::Placeholder(rest[2..end].trim()));
+                    idx += end + 2;
+                    start = idx;
+                } else {
+                    idx += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                }
+            }
+        }
+    }
+
+    flush!();
+
+    Ok(out)
+}
+
+/// How a variable is formatted
+#[derive(Debug, Clone, PartialEq)]
+enum Format {
+    Plain,
+    /// A `strfmt` format spec, e.g. `name:>10`
+    Spec(String),
+    /// A hint, e.g. `number, percent`, with the name of its kind (`number`)
+    Hint(Hint, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    /// `$t(key)`
+    Link(String),
+    Var {
+        name: String,
+        format: Format,
+        /// The placeholder as written, for `MissingVar::Keep`
+        placeholder: String,
+    },
+}
+
+/// A (non-ICU) message, parsed once by `Config::finish`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Template(Vec<Part>);
+
+impl Template {
+    /// Parse `message` with `syntax` placeholders, checking format specs and hints.
+    pub(crate) fn compile(message: &str, syntax: Interpolation) -> err::Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+
+        for piece in link::pieces(message) {
+            let segments = match piece {
+                link::Piece::Text(segment) => parse(segment, syntax)?,
+                link::Piece::Link(key) => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Link(key.into()));
+                    continue;
+                }
+            };
+            for segment in segments {
+                match segment {
+                    Segment::Text(segment) => text.push_str(segment),
+                    Segment::Placeholder(inner) => {
+                        if !text.is_empty() {
+                            parts.push(Part::Text(std::mem::take(&mut text)));
+                        }
+                        parts.push(Self::var(inner, syntax)?);
+                    }
+                }
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        if !parts.iter().any(|part| matches!(part, Part::Var { .. })) {
+            parts = link::pieces(message)
+                .into_iter()
+                .map(|piece| match piece {
+                    link::Piece::Text(text) => Part::Text(text.into()),
+                    link::Piece::Link(key) => Part::Link(key.into()),
+                })
+                .collect();
+        }
+
+        Ok(Template(parts))
+    }
+
+    /// Parse the placeholder `inner`, e.g. `name`, `name:>10` or `name, number`.
+    fn var(inner: &str, syntax: Interpolation) -> err::Result<Part> {
+        let (name, format) = match inner.find([':', ',']) {
+            Some(idx) if inner[idx..].starts_with(',') => {
+                let name = inner[..idx].trim();
+                let hint = &inner[idx + 1..];
+                let mut parts = hint.splitn(2, ',').map(str::trim);
+                let kind = parts.next().unwrap_or_default();
+                let format = Hint::parse(kind, parts.next())
+                    .map(|parsed| Format::Hint(parsed, kind.into()))
+                    .ok_or_else(|| {
+                        err::custom(format!("Invalid format for `{}`: {}", name, hint))
+                    })?;
+                (name, format)
+            }
+            Some(idx) => {
+                // Check the spec now rather than on first use
+                strfmt::Formatter::from_str(inner, &mut String::new())?;
+                (&inner[..idx], Format::Spec(inner.into()))
+            }
+            None => (inner, Format::Plain),
+        };
+        let placeholder = match syntax {
+            Interpolation::Brace => format!("{{{}}}", inner),
+            Interpolation::Percent => format!("%{{{}}}", inner),
+            Interpolation::DoubleBrace => format!("{{{{{}}}}}", inner),
+        };
+        Ok(Part::Var { name: name.into(), format, placeholder })
+    }
+
+    /// Format the message to `out` with `vars`, following `$t(key)` links with `link`.
+    ///
+    /// With `rich`, variables are added to it as their own pieces, for `translate_rich`.
+    pub(crate) fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        vars: &Vars,
+        missing: &Missing,
+        link: &LinkFn,
+        rich: Option<&rich::Pieces>,
+    ) -> err::Result<()> {
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => vars.escape.text(out, text)?,
+                Part::Link(key) => link(out, key)?,
+                Part::Var { name, format, placeholder } => match vars.get(name) {
+                    Some(value) => {
+                        let write =
+                            |out: &mut dyn fmt::Write| write_var(out, vars, name, value, format);
+                        match rich {
+                            Some(rich) => rich.var(name, write)?,
+                            None => write(out)?,
+                        }
+                    }
+                    None => {
+                        let replacement = missing.replace(name, placeholder).ok_or_else(|| {
+                            err::Error::Strfmt(strfmt::FmtError::KeyError(format!(
+                                "Invalid key: {}",
+                                name
+                            )))
+                        })?;
+                        vars.escape.value(out, replacement)?
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// The message text, if it has no placeholders or links.
+    pub(crate) fn plain(&self) -> Option<&str> {
+        match &self.0[..] {
+            [] => Some(""),
+            [Part::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Write the variable `name` with `value` to `out`.
+fn write_var(
+    out: &mut dyn fmt::Write,
+    vars: &Vars,
+    name: &str,
+    value: &Value,
+    format: &Format,
+) -> err::Result<()> {
+    match format {
+        Format::Plain => out.write_str(&vars.format(value, None)?.unwrap_or_default())?,
+        Format::Spec(spec) => {
+            // `strfmt` only formats into a `String`
+            let mut buf = String::new();
+            let mut formatter = strfmt::Formatter::from_str(spec, &mut buf)?;
+            match value {
+                Value::Integer(n) => formatter.i64(*n)?,
+                value => formatter.str(&vars.format(value, None)?.unwrap_or_default())?,
+            }
+            out.write_str(&buf)?
+        }
+        Format::Hint(hint, kind) => {
+            out.write_str(&vars.format(value, Some(hint))?.ok_or_else(|| {
+                err::custom(format!(
+                    "Variable `{}` does not fit the {} format: {}",
+                    name, kind, value
+                ))
+            })?)?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{
+        Interpolation, Interpolation::*, Missing, MissingVar, Template, Value, Vars, Warn,
+    };
+    use crate::{err, html::Escape, value::VarMap, DateTime};
+    use std::sync::{Arc, Mutex};
+
+    fn interpolate(
+        text: &str,
+        syntax: Interpolation,
+        vars: &Vars,
+        missing: &Missing,
+    ) -> err::Result<String> {
+        let link = |out: &mut dyn std::fmt::Write, key: &str| -> err::Result<()> {
+            Ok(out.write_str(&key.to_uppercase())?)
+        };
+        let mut out = String::new();
+        Template::compile(text, syntax)?.format(&mut out, vars, missing, &link, None)?;
+        Ok(out)
+    }
+
+    fn error() -> Missing<'static> {
+        Missing { policy: &MissingVar::Error, warn: None, locale: "en", key: &"key" }
+    }
+
+    fn iso(value: &DateTime, _: &str) -> err::Result<String> {
+        Ok(Value::from(*value).to_string())
+    }
+
+    fn translate(key: &str) -> err::Result<String> {
+        Ok(key.to_uppercase())
+    }
+
+    fn vars<'a>(locale: &'a str, values: &'a VarMap) -> Vars<'a> {
+        Vars { locale, values, localize: &iso, translate: &translate, escape: Escape::Nothing }
+    }
+
+    #[test]
+    fn interpolates() {
+        let mut values = VarMap::default();
+        values.insert(String::from("name"), Value::from("Jacob"));
+        values.insert(String::from("count"), Value::from(7));
+        let en = vars("en", &values);
+        let missing = error();
+
+        assert_eq!(interpolate("Hi {name}! {{x}}", Brace, &en, &missing).unwrap(), "Hi Jacob! {x}");
+        assert_eq!(interpolate("[{name:>7}]", Brace, &en, &missing).unwrap(), "[  Jacob]");
+        assert!(interpolate("Hi {name", Brace, &en, &missing).is_err());
+        assert!(interpolate("Hi {other}", Brace, &en, &missing).is_err());
+
+        assert_eq!(
+            interpolate("Hi %{name}, {name} 100%% %%{name}", Percent, &en, &missing).unwrap(),
+            "Hi Jacob, {name} 100% %{name}"
+        );
+        assert_eq!(interpolate("50% off", Percent, &en, &missing).unwrap(), "50% off");
+
+        // Messages without placeholders are used as written
+        assert_eq!(interpolate("{{x}} 100%%", Brace, &en, &missing).unwrap(), "{{x}} 100%%");
+        assert_eq!(interpolate("100%%", Percent, &en, &missing).unwrap(), "100%%");
+        assert_eq!(interpolate("\\{{x}}", DoubleBrace, &en, &missing).unwrap(), "\\{{x}}");
+
+        assert_eq!(
+            interpolate("Hi {{ name }}, {name} \\{{name}}", DoubleBrace, &en, &missing).unwrap(),
+            "Hi Jacob, {name} {{name}}"
+        );
+        assert!(interpolate("Hi {{name", DoubleBrace, &en, &missing).is_err());
+
+        assert_eq!(
+            interpolate("{count} [{count:>3}] [{count:+}]", Brace, &en, &missing).unwrap(),
+            "7 [  7] [+7]"
+        );
+        assert_eq!(
+            interpolate("$t(app.name), {name}", Brace, &en, &missing).unwrap(),
+            "APP.NAME, Jacob"
+        );
+        assert!(Template::compile("{count:^^^}", Brace).is_err());
+        assert!(Template::compile("{count, currency, nope}", Brace).is_err());
+    }
+
+    #[test]
+    fn number_hints() {
+        let mut values = VarMap::default();
+        values.insert(String::from("total"), Value::from("1234567.5"));
+        values.insert(String::from("ratio"), Value::from(0.25));
+        values.insert(String::from("name"), Value::from("Jacob"));
+        values.insert(String::from("since"), Value::from(DateTime::date(2020, 3, 4)));
+        let (en, de) = (vars("en", &values), vars("de", &values));
+        let missing = error();
+
+        assert_eq!(
+            interpolate("{total, number} ({ratio, percent})", Brace, &de, &missing).unwrap(),
+            "1.234.567,5 (25\u{a0}%)"
+        );
+        assert_eq!(
+            interpolate("%{total, number, compact}", Percent, &en, &missing).unwrap(),
+            "1.2M"
+        );
+        assert_eq!(
+            interpolate("{{ total, number, integer }}", DoubleBrace, &en, &missing).unwrap(),
+            "1,234,568"
+        );
+        assert_eq!(
+            interpolate("{total, currency, EUR}", Brace, &de, &missing).unwrap(),
+            "1.234.567,50\u{a0}€"
+        );
+        assert!(interpolate("{name, number}", Brace, &en, &missing).is_err());
+        assert!(interpolate("{total, currency}", Brace, &en, &missing).is_err());
+        assert!(interpolate("{total, date}", Brace, &en, &missing).is_err());
+        assert_eq!(
+            interpolate("{since} ({since, date, long})", Brace, &en, &missing).unwrap(),
+            "2020-03-04 (2020-03-04)"
+        );
+    }
+
+    #[test]
+    fn missing_vars() {
+        let warned = Arc::new(Mutex::new(Vec::new()));
+        let warn = {
+            let warned = warned.clone();
+            Warn(Arc::new(move |locale: &str, key: &str, var: &str| {
+                warned.lock().unwrap().push(format!("{}.{}: {}", locale, key, var))
+            }))
+        };
+        let empty_vars = VarMap::default();
+        let none = vars("en", &empty_vars);
+        let missing = |policy| Missing { policy, warn: Some(&warn), locale: "en", key: &"key" };
+
+        let keep = missing(&MissingVar::Keep);
+        assert_eq!(interpolate("Hi {name:>7}!", Brace, &none, &keep).unwrap(), "Hi {name:>7}!");
+        assert_eq!(interpolate("Hi %{name}!", Percent, &none, &keep).unwrap(), "Hi %{name}!");
+        assert_eq!(interpolate("Hi {{name}}!", DoubleBrace, &none, &keep).unwrap(), "Hi {{name}}!");
+
+        let empty = missing(&MissingVar::Empty);
+        assert_eq!(interpolate("Hi {name}!", Brace, &none, &empty).unwrap(), "Hi !");
+
+        let marker = MissingVar::Marker(String::from("[?]"));
+        assert_eq!(interpolate("Hi {name}!", Brace, &none, &missing(&marker)).unwrap(), "Hi [?]!");
+
+        assert_eq!(warned.lock().unwrap().len(), 5);
+        assert_eq!(warned.lock().unwrap()[0], "en.key: name");
+
+        assert!(interpolate("Hi {name}!", Brace, &none, &error()).is_err());
+    }
+}
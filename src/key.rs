@@ -1,53 +1,37 @@
+use std::borrow::Cow;
+
 #[doc(hidden)]
 #[derive(Clone)]
 pub enum Key<'a> {
     Str(&'a str),
     Slice(&'a [&'a str]),
-    Pair(Box<Key<'a>>, Box<Key<'a>>),
 }
 
 impl<'a> Key<'a> {
-    fn dig<I: Iterator<Item = &'a str>>(
-        path: I,
-        mut value: &serde_json::Value,
-    ) -> Option<&serde_json::Value> {
-        for part in path {
-            let value_opt = match value {
-                serde_json::Value::Object(ref map) => map.get(part),
-                serde_json::Value::Array(ref arr) => {
-                    part.parse::<usize>().ok().and_then(|i| arr.get(i))
-                }
-                _ => None,
-            };
-            value = match value_opt {
-                Some(value) => value,
-                None => {
-                    return None;
-                }
-            }
+    /// Get the dot-delimited path, borrowed for `&str` keys.
+    pub(crate) fn path(&self) -> Cow<'a, str> {
+        match self {
+            Key::Str(s) => Cow::Borrowed(s),
+            Key::Slice(s) => Cow::Owned(s.join(".")),
         }
-        Some(value)
     }
+}
 
-    fn iter(&self) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+impl<'a> std::fmt::Display for Key<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Key::Str(s) => Box::new(s.split('.')),
-            Key::Slice(s) => Box::new(s.into_iter().map(|x| *x)),
-            Key::Pair(a, b) => Box::new(a.iter().chain(b.iter())),
+            Key::Str(s) => f.write_str(s),
+            Key::Slice(s) => {
+                for (i, part) in s.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    f.write_str(part)?;
+                }
+                Ok(())
+            }
         }
     }
-
-    pub(crate) fn chain<I: Into<Self>>(self, other: I) -> Self {
-        Key::Pair(Box::new(self), Box::new(other.into()))
-    }
-
-    pub(crate) fn find(&'a self, value: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
-        Self::dig(self.iter(), value)
-    }
-
-    pub(crate) fn to_string(&self) -> String {
-        self.iter().collect::<Vec<_>>().join(".")
-    }
 }
 
 impl<'a> From<&'a str> for Key<'a> {
@@ -52,12 +52,96 @@
 //! }
 //! ```
 //!
-//! ## Features
+//! ## Loading
 //!
 //! Translation files can be:
 //! * JSON
 //! * YAML (enabled by default, disable with `default-features = false`), or
 //! * TOML (enable with `features = ["toml"]`).
+//!
+//! Messages are parsed once by `Config::finish`. A syntax error is reported (with the locale and
+//! key) when that message is used, or by `finish` with `Config::with_strict(true)`. Messages are
+//! indexed by their flattened, dot-delimited key so `&str` keys are looked up without allocating
+//! (`cargo bench --bench lookup` compares this with walking a JSON tree).
+//!
+//! `Config::with_pseudo_locale` generates pseudo-locales from the default locale, to find
+//! hard-coded strings and layout bugs: `PseudoLocale::accented()` (`en-XA`, "[Ĥéļļö, {name}! one]")
+//! and `PseudoLocale::bidi()` (`ar-XB`, mirrored right-to-left). Placeholders and markup are kept.
+//!
+//! ## Interpolation
+//!
+//! Messages use `{name}` placeholders by default, or ICU MessageFormat syntax (`plural`,
+//! `selectordinal`, `select`) with `Config::with_message_format(MessageFormat::Icu)`.
+//! Use `Config::with_interpolation(Interpolation::Percent)` for ruby-i18n style `%{name}`
+//! placeholders, or `Interpolation::DoubleBrace` for `{{name}}`.
+//!
+//! Variables are typed `Value`s: integers, decimals, strings, dates, lists and nested translations
+//! (`Value::translation("other.key")`). Dates use the `default` format of the locale, or the one in
+//! a `{joined, date, long}` hint. `Opts::vars_from` sets variables from any `serde::Serialize`
+//! struct or map, with nested fields as dotted names (`{user.name}`).
+//!
+//! By default a placeholder without a matching variable is an error if the call passes variables,
+//! and is left as is if it passes none; use `Config::with_missing_var` (or `Opts::missing_var`)
+//! to always fail, keep the placeholder, or substitute an empty string or a marker, and
+//! `Config::with_missing_var_hook` to be notified.
+//!
+//! Messages can include other messages with `$t(other.key)`, which are translated with the
+//! locale and variables of the call (not its count, selects or `default_key`). A message that is
+//! only `$t(other.key)` is an alias. ruby-i18n `:other.key` symbols are plain text.
+//!
+//! `Dictionary::translate_to` writes the message to any `fmt::Write` (such as a template engine's
+//! output buffer), `Dictionary::lazy` returns a value that translates when displayed, and
+//! `Dictionary::translate_cow` borrows messages without placeholders from the dictionary.
+//!
+//! ## Plurals
+//!
+//! `Count(n)` picks the child of a message named for the CLDR plural category of `n` in the
+//! locale of the call (`one`, `few`, `other`, ...), or `zero` for `0` if there is one, and
+//! `Ordinal(n)` uses the ordinal categories ("23rd"). `Select("gender", "female")` picks a child
+//! by name first. `Config::with_plural_rule` overrides the rule of a locale. In ICU messages, any
+//! number variable can select a `plural` or `selectordinal` branch.
+//!
+//! ## Formatting
+//!
+//! Numbers are formatted with CLDR symbols for the locale of the call using placeholder hints:
+//! `{total, number}`, `{total, number, integer}`, `{ratio, percent}` or `{total, number, compact}`
+//! ("1.2K"); see `Dictionary::format_number`. Prices use `{price, currency}` with a `Money`
+//! variable, or `Dictionary::format_currency`.
+//!
+//! Dates and times are localized ruby-i18n style with `localize` (or `l`), using the named
+//! formats and month/day names in the `date` and `time` entries of the locale files. Enable the
+//! `chrono` feature to localize `chrono` dates and times.
+//!
+//! Relative times ("3 minutes ago", "in 2 days", "yesterday") are formatted with
+//! `Dictionary::relative_time`, from `relative_time` entries in the locale files (English is
+//! bundled).
+//!
+//! ## HTML and rich text
+//!
+//! `translate_html` returns `SafeHtml`, Rails style: messages for keys ending in `_html` (or
+//! `.html`) are trusted markup with escaped variables, and all other messages are escaped in full.
+//! This holds for every message used, including `$t()` links and `default_key` fallbacks.
+//!
+//! `translate_rich` splits messages with `<tag>...</tag>` markup (`Read our <link>terms</link>`)
+//! into `RichSegment`s of text, tags and variables, for rendering to HTML, a terminal or a UI
+//! toolkit, and `TagRenderer` renders them with a closure for each tag.
+//!
+//! ## Catalogs
+//!
+//! `Dictionary::write_catalog` compiles the loaded messages into a compact binary catalog (a
+//! string table and a sorted key index per locale), and `Config::with_catalog` loads it without
+//! deserializing a tree: memory-mapped with `features = ["mmap"]`, so worker processes share its
+//! pages.
+//!
+//! ## Global API
+//!
+//! `loon::reload()` rebuilds the global dictionary from its configuration, and
+//! `loon::set_dictionary` replaces it with any `Dictionary`, so long-running services can pick up
+//! new translations. The swap is atomic and readers don't lock, and a failed `reload` keeps the
+//! current dictionary. `set_config` can also be called again; like the first configuration, the
+//! new one is loaded on the next global call.
+
+#![allow(clippy::needless_doctest_main)]
 
 /// Error management
 pub mod err {
@@ -69,6 +153,8 @@ pub mod err {
         Io(#[from] std::io::Error),
         #[error("strfmt error: `{0}`")]
         Strfmt(#[from] strfmt::FmtError),
+        #[error("Write error: `{0}`")]
+        Fmt(#[from] std::fmt::Error),
         #[cfg(feature = "yaml")]
         #[error("YAML error: {0}")]
         Yaml(#[from] serde_yaml::Error),
@@ -83,6 +169,10 @@ pub mod err {
         UnknownLocale(Box<str>),
         #[error("Unknown key: {0}")]
         UnknownKey(Box<str>),
+        #[error("Message format error: {0}")]
+        MessageFormat(Box<str>),
+        #[error("Link error: {0}")]
+        Link(Box<str>),
     }
 
     /// Create a custom error.
@@ -93,21 +183,40 @@ pub mod err {
     pub type Result<T> = std::result::Result<T, Error>;
 }
 
+mod catalog;
 mod config;
+mod currency;
+mod date;
+mod global;
+mod html;
+mod icu;
+mod index;
+mod interpolate;
 mod key;
+mod link;
+mod list;
+mod number;
 mod opts;
+mod plural;
+mod pseudo;
+mod relative;
+mod rich;
+mod value;
 
 /// Helpers to build `Config` or `Opts` items
 pub mod helpers {
 
     /// Helpers to build `Config` items
     pub mod config {
-        pub use crate::config::{DefaultLocale, LocalizedPath, PathPattern};
+        pub use crate::config::{
+            CatalogPath, DefaultLocale, Interpolation, LocalizedPath, MessageFormat, MissingVar,
+            MissingVarHook, PathPattern, PluralRule, PseudoLocale,
+        };
     }
 
     /// Helpers to build `Opts` items
     pub mod opts {
-        pub use crate::opts::{Count, DefaultKey, Locale, Var};
+        pub use crate::opts::{Count, DefaultKey, List, Locale, Ordinal, Select, Var};
     }
 }
 
@@ -117,7 +226,10 @@ pub mod helpers {
 /// pub use loon::{
 ///     helpers::{config::*, opts::*},
 ///     Config,
+///     CurrencyStyle,
 ///     Dictionary,
+///     ListType,
+///     NumberStyle,
 ///     Opts,
 ///     translate,
 ///     t
@@ -127,30 +239,65 @@ pub mod prelude {
     pub use crate::{
         helpers::{config::*, opts::*},
         Config,
+        CurrencyStyle,
         Dictionary,
+        ListType,
+        NumberStyle,
         Opts,
         translate,
         t
     };
 }
 
-use once_cell::sync::{Lazy, OnceCell};
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fmt};
 
 pub use config::Config;
+pub use currency::{CurrencyStyle, Money};
+pub use date::DateTime;
+pub use html::SafeHtml;
 pub use key::Key;
+pub use list::ListType;
+pub use number::{Decimal, NumberStyle};
 pub use opts::Opts;
+pub use plural::PluralOperands;
+pub use relative::{RelativeNumeric, RelativeStyle, RelativeTime};
+pub use rich::{RichSegment, TagRenderer};
+pub use value::Value;
 
 /// Container for translation messages
 #[derive(Debug)]
 pub struct Dictionary {
-    inner: HashMap<String, serde_json::Value>,
     default_locale: String,
+    plural_rules: HashMap<String, plural::CustomRule>,
+    /// Flattened messages by locale
+    index: HashMap<String, index::Index>,
+    missing_var: Option<interpolate::MissingVar>,
+    missing_var_hook: Option<interpolate::Warn>,
+}
+
+/// A translation that is formatted when displayed (see `Dictionary::lazy`)
+#[derive(Clone)]
+pub struct LazyTranslation<'d, 'a> {
+    dict: &'d Dictionary,
+    key: Key<'a>,
+    opts: Opts<'a>,
+}
+
+impl fmt::Display for LazyTranslation<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.dict.translate_to(f, self.key.clone(), self.opts.clone()).map_err(|_| fmt::Error)
+    }
 }
 
 impl Default for Dictionary {
     fn default() -> Self {
-        Self { inner: HashMap::new(), default_locale: "en".into() }
+        Self {
+            default_locale: "en".into(),
+            plural_rules: HashMap::new(),
+            index: HashMap::new(),
+            missing_var: None,
+            missing_var_hook: None,
+        }
     }
 }
 
@@ -159,8 +306,8 @@ impl Dictionary {
     ///
     /// `key` can be a dot-delimited `&str` or a `&[&str]` path.
     ///
-    /// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Locale`, or `DefaultKey` (or up
-    /// to a `4-tuple` of these items).
+    /// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Ordinal`, `Select`, `Locale`,
+    /// `DefaultKey`, or `MissingVar` (or up to a `4-tuple` of these items).
     ///
     /// Examples:
     /// ```rust
@@ -176,66 +323,493 @@ impl Dictionary {
         key: K,
         opts: I,
     ) -> err::Result<String> {
+        let mut out = String::new();
+        self.translate_to(&mut out, key, opts)?;
+        Ok(out)
+    }
+
+    /// Write the translated message to `out`, e.g. the output buffer of a template engine,
+    /// without building an intermediate `String`.
+    ///
+    /// On error, part of the message may already have been written.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// let mut out = String::from("<p>");
+    /// dict.translate_to(&mut out, "custom.greeting", Var("name", "Jacob")).unwrap();
+    /// assert_eq!(out, "<p>Hello, Jacob!!!");
+    /// ```
+    pub fn translate_to<'a, W, K, I>(&self, out: &mut W, key: K, opts: I) -> err::Result<()>
+    where
+        W: fmt::Write,
+        K: Into<Key<'a>>,
+        I: Into<Opts<'a>>,
+    {
         let opts = opts.into();
+        let locale = opts.locale.unwrap_or_else(|| &self.default_locale);
+        self.resolve(out, locale, key.into(), &opts, &[])
+    }
 
-        let mut key = key.into();
+    /// Get the translated message, borrowed from the dictionary when the message has no
+    /// placeholders or links.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// use std::borrow::Cow;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// assert!(matches!(dict.translate_cow("greeting", None).unwrap(), Cow::Borrowed("Hello, World!")));
+    /// assert!(matches!(dict.translate_cow("messages", Count(2)).unwrap(), Cow::Owned(_)));
+    /// ```
+    pub fn translate_cow<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(
+        &self,
+        key: K,
+        opts: I,
+    ) -> err::Result<Cow<'_, str>> {
+        let key = key.into();
+        let opts = opts.into();
 
-        let alt_key;
+        let locale = opts.locale.unwrap_or_else(|| &self.default_locale);
 
-        match opts.count {
-            Some(0) => {
-                alt_key = key.chain(["zero"].as_ref());
-                key = alt_key;
-            }
-            Some(1) => {
-                alt_key = key.chain(["one"].as_ref());
-                key = alt_key;
-            }
-            Some(_) => {
-                alt_key = key.chain(["other"].as_ref());
-                key = alt_key;
+        if let Some(index) = self.index.get(locale) {
+            if let Ok((_, message, _)) = self.entry(locale, index, key.clone(), &opts) {
+                if let Some(text) = message.compiled().ok().and_then(index::Compiled::plain) {
+                    return Ok(Cow::Borrowed(text));
+                }
             }
-            _ => {}
         }
 
+        self.translate(key, opts).map(Cow::Owned)
+    }
+
+    /// Get a value that translates the message when displayed, e.g. with `write!` to an
+    /// `io::Write` sink or as a `format!` argument, without building an intermediate `String`.
+    ///
+    /// Displaying fails with `fmt::Error` if the translation fails; use `translate_to` to get the
+    /// error.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// let greeting = dict.lazy("custom.greeting", Var("name", "Jacob"));
+    /// assert_eq!(format!("<p>{}</p>", greeting), "<p>Hello, Jacob!!!</p>");
+    /// ```
+    pub fn lazy<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(
+        &self,
+        key: K,
+        opts: I,
+    ) -> LazyTranslation<'_, 'a> {
+        LazyTranslation { dict: self, key: key.into(), opts: opts.into() }
+    }
+
+    /// Get the translated message as HTML, Rails style.
+    ///
+    /// Messages for keys ending in `_html` (or `.html`) are trusted markup: only the interpolated
+    /// variables (and missing-variable replacements) are escaped. All other messages are plain
+    /// text, and are escaped in full. This is decided for each message that is used, so a
+    /// `default_key` or `$t()` link to a plain message is escaped even from an `_html` key.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// let html = dict.translate_html("terms_html", Var("name", "<Jacob>")).unwrap();
+    /// assert_eq!(html.as_str(), r#"&lt;Jacob&gt;, please accept the <a href="/terms">terms</a>."#);
+    /// ```
+    pub fn translate_html<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(
+        &self,
+        key: K,
+        opts: I,
+    ) -> err::Result<SafeHtml> {
+        let mut opts = opts.into();
+        opts.html = true;
         let locale = opts.locale.unwrap_or_else(|| &self.default_locale);
 
-        let localized = self
-            .inner
-            .get(locale)
-            .ok_or_else(|| err::Error::UnknownLocale(String::from(locale).into_boxed_str()))?;
+        let mut out = String::new();
+        self.resolve(&mut out, locale, key.into(), &opts, &[])?;
+        Ok(SafeHtml::trusted(out))
+    }
+
+    /// Get the translated message as rich text: `<tag>...</tag>` (or `<tag/>`) markup in the
+    /// message becomes `RichSegment::Tag`s, and interpolated variables `RichSegment::Var`s, for
+    /// the caller to render (e.g. with a `TagRenderer`).
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::{prelude::*, RichSegment, TagRenderer};
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// let segments = dict.translate_rich("rich.terms", Var("name", "Jacob")).unwrap();
+    /// assert_eq!(segments[0], RichSegment::Var { name: "name".into(), value: "Jacob".into() });
+    /// let out = TagRenderer::new()
+    ///     .tag("link", |s| format!("[{}](/terms)", s))
+    ///     .tag("b", |s| format!("**{}**", s))
+    ///     .render(&segments);
+    /// assert_eq!(out, "Jacob, read our [terms](/terms) and **privacy policy**.");
+    /// ```
+    pub fn translate_rich<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(
+        &self,
+        key: K,
+        opts: I,
+    ) -> err::Result<Vec<RichSegment>> {
+        let pieces = rich::Pieces::default();
+        let mut opts: Opts = opts.into();
+        opts.rich = Some(&pieces);
+        let locale = opts.locale.unwrap_or_else(|| &self.default_locale);
+        self.resolve(&mut &pieces, locale, key.into(), &opts, &[])?;
+        rich::parse(pieces)
+    }
+
+    /// Find the message for `key` (applying `select`, pluralization and `default_key`), its
+    /// path, and whether it is trusted HTML (the key it was found for is an `_html` key).
+    fn entry<'k, 'i>(
+        &self,
+        locale: &str,
+        index: &'i index::Index,
+        key: Key<'k>,
+        opts: &Opts<'k>,
+    ) -> err::Result<(Cow<'k, str>, index::Message<'i>, bool)> {
+        let mut path = key.path();
+        let html = html::is_html_key(&path);
+
+        for value in &opts.selects {
+            if let Some(branch) = index.branch(&path) {
+                let name = if branch.contains(value) { *value } else { "other" };
+                path = Cow::Owned(format!("{}.{}", path, name));
+            }
+        }
 
-        let entry = |key: Key| {
-            key.find(localized)
-                .and_then(|val| val.as_str())
-                .map(String::from)
-                .ok_or_else(|| err::Error::UnknownKey(key.to_string().into_boxed_str()))
+        if let Some(branch) = index.branch(&path) {
+            let category = if let Some(ref count) = opts.ordinal {
+                Some(plural::ordinal(locale, &count.into()).as_str())
+            } else if let Some(ref count) = opts.count {
+                Some(if count.is_zero() && branch.contains("zero") {
+                    "zero"
+                } else {
+                    self.cardinal(locale, count)
+                })
+            } else {
+                None
+            };
+            if let Some(category) = category {
+                let name = if branch.contains(category) { category } else { "other" };
+                path = Cow::Owned(format!("{}.{}", path, name));
+            }
+        }
+
+        let entry = |path: Cow<'k, str>, html: bool| match index.message(&path) {
+            Some(message) => Ok((path, message, html)),
+            None => Err(err::Error::UnknownKey(path.into_owned().into_boxed_str())),
         };
 
-        let value = match entry(key) {
-            Ok(value) => value,
+        match entry(path, html) {
+            Ok(entry) => Ok(entry),
             Err(e) => match opts.default_key {
-                Some(default_key) => {
-                    return entry(default_key);
-                }
-                _ => {
-                    return Err(e);
+                Some(ref default_key) => {
+                    let path = default_key.path();
+                    let html = html::is_html_key(&path);
+                    entry(path, html)
                 }
+                _ => Err(e),
             },
+        }
+    }
+
+    /// Find and format the message for `key` to `out`, following links to other messages.
+    ///
+    /// `links` are the keys of the links followed so far.
+    fn resolve<'k>(
+        &self,
+        out: &mut dyn fmt::Write,
+        locale: &str,
+        key: Key<'k>,
+        opts: &Opts<'k>,
+        links: &[&str],
+    ) -> err::Result<()> {
+        let index = self
+            .index
+            .get(locale)
+            .ok_or_else(|| err::Error::UnknownLocale(String::from(locale).into_boxed_str()))?;
+        let (path, message, trusted) = self.entry(locale, index, key, opts)?;
+
+        let missing = interpolate::Missing {
+            policy: opts.missing_var.as_ref().or(self.missing_var.as_ref()).unwrap_or(
+                if opts.vars.is_empty() {
+                    &interpolate::MissingVar::Keep
+                } else {
+                    &interpolate::MissingVar::Error
+                },
+            ),
+            warn: self.missing_var_hook.as_ref(),
+            locale,
+            key: &path,
+        };
+        let localize = |value: &DateTime, format: &str| self.format_date(index, value, format);
+        let translate = |key: &str| {
+            let nested = Opts::default().locale(locale);
+            if opts.html {
+                self.translate_html(key, nested).map(SafeHtml::into_string)
+            } else {
+                self.translate(key, nested)
+            }
+        };
+        let escape = match (opts.html, trusted) {
+            (false, _) => html::Escape::Nothing,
+            (true, true) => html::Escape::Values,
+            (true, false) => html::Escape::All,
+        };
+        let vars = value::Vars {
+            locale,
+            values: &opts.vars,
+            localize: &localize,
+            translate: &translate,
+            escape,
+        };
+        let link =
+            |out: &mut dyn fmt::Write, target: &str| self.follow(out, locale, target, opts, links);
+
+        match message.compiled()? {
+            index::Compiled::Icu(message) => {
+                let plural = |count: &Decimal, ordinal: bool| {
+                    if ordinal {
+                        plural::ordinal(locale, &count.into()).as_str()
+                    } else {
+                        self.cardinal(locale, count)
+                    }
+                };
+                message.format(
+                    out,
+                    &icu::Env {
+                        vars: &vars,
+                        plural: &plural,
+                        link: &link,
+                        missing: &missing,
+                        rich: opts.rich,
+                    },
+                )
+            }
+            index::Compiled::Template(template) => {
+                template.format(out, &vars, &missing, &link, opts.rich)
+            }
+        }
+    }
+
+    /// Follow a link to `target`, checking for cycles and excessive nesting.
+    fn follow(
+        &self,
+        out: &mut dyn fmt::Write,
+        locale: &str,
+        target: &str,
+        opts: &Opts,
+        links: &[&str],
+    ) -> err::Result<()> {
+        if links.contains(&target) {
+            return Err(err::Error::Link(
+                format!("cycle detected: {} -> {}", links.join(" -> "), target).into_boxed_str(),
+            ));
+        }
+        if links.len() >= link::MAX_DEPTH {
+            return Err(err::Error::Link(
+                format!("more than {} nested links at: {}", link::MAX_DEPTH, target)
+                    .into_boxed_str(),
+            ));
+        }
+        let mut links = links.to_vec();
+        links.push(target);
+        // The count, selects and `default_key` are for the message that links here.
+        let opts = Opts {
+            vars: opts.vars.clone(),
+            locale: Some(locale),
+            missing_var: opts.missing_var.clone(),
+            rich: opts.rich,
+            html: opts.html,
+            ..Opts::default()
         };
+        self.resolve(out, locale, Key::Str(target), &opts, &links)
+    }
 
-        match opts.vars {
-            Some(vars) => Ok(strfmt::strfmt(&value, &vars)?),
-            None => Ok(value),
+    /// Get the cardinal plural category name for `count`, preferring a custom rule for `locale`
+    /// (or its language) over the CLDR rules.
+    fn cardinal(&self, locale: &str, count: &Decimal) -> &'static str {
+        let ops = count.into();
+        match self
+            .plural_rules
+            .get(locale)
+            .or_else(|| self.plural_rules.get(&plural::language(locale)))
+        {
+            Some(rule) => (rule.0)(&ops),
+            None => plural::cardinal(locale, &ops).as_str(),
         }
     }
 
+    /// Join `items` with the CLDR list pattern for `locale` (e.g. "A, B, and C").
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Dictionary::default();
+    /// assert_eq!(dict.format_list("de", &["A", "B", "C"], ListType::Conjunction), "A, B und C");
+    /// assert_eq!(dict.format_list("en", &["A", "B"], ListType::Disjunction), "A or B");
+    /// ```
+    pub fn format_list<S: AsRef<str>>(
+        &self,
+        locale: &str,
+        items: &[S],
+        list_type: ListType,
+    ) -> String {
+        list::format(locale, list_type, items)
+    }
+
+    /// Format `number` with the CLDR symbols for `locale` (e.g. "1,234.5" or "1.234,5").
+    ///
+    /// Messages can do the same with placeholder hints: `{total, number}`, `{ratio, percent}`,
+    /// `{total, number, integer}` or `{total, number, compact}`.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Dictionary::default();
+    /// assert_eq!(dict.format_number("en", 1234567.5, NumberStyle::Decimal), "1,234,567.5");
+    /// assert_eq!(dict.format_number("de", 1234567.5, NumberStyle::Decimal), "1.234.567,5");
+    /// assert_eq!(dict.format_number("en", 0.25, NumberStyle::Percent), "25%");
+    /// assert_eq!(dict.format_number("en", 1234, NumberStyle::Compact), "1.2K");
+    /// ```
+    pub fn format_number<N: Into<Decimal>>(
+        &self,
+        locale: &str,
+        number: N,
+        style: NumberStyle,
+    ) -> String {
+        number::format(locale, &number.into(), style)
+    }
+
+    /// Format `amount` in the currency with ISO 4217 `code` for `locale`, with the CLDR symbol
+    /// placement and the currency's decimal digits (e.g. "$1,234.50", "1.234,50 €" or "￥1,234").
+    ///
+    /// Messages can do the same with `{price, currency}` (passing e.g. `Money::new(12.5, "EUR")`),
+    /// `{price, currency, EUR}` or `{price, currency, accounting}` placeholders.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Dictionary::default();
+    /// assert_eq!(dict.format_currency("en", 1234.5, "USD", CurrencyStyle::Standard), "$1,234.50");
+    /// assert_eq!(dict.format_currency("en", -5, "USD", CurrencyStyle::Accounting), "($5.00)");
+    /// ```
+    pub fn format_currency<N: Into<Decimal>>(
+        &self,
+        locale: &str,
+        amount: N,
+        code: &str,
+        style: CurrencyStyle,
+    ) -> String {
+        currency::format(locale, &amount.into(), code, style)
+    }
+
+    /// Get the locales for which messages have been loaded, in sorted order.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// assert_eq!(dict.locales(), vec!["de", "en", "ru"]);
+    /// ```
+    pub fn locales(&self) -> Vec<&str> {
+        let mut out = self.index.keys().map(String::as_str).collect::<Vec<_>>();
+        out.sort_unstable();
+        out
+    }
+
+    /// Check whether `key` exists (as a message or a subtree) for `locale`.
+    ///
+    /// `key` can be a dot-delimited `&str` or a `&[&str]` path.
+    pub fn has_key<'a, K: Into<Key<'a>>>(&self, locale: &str, key: K) -> bool {
+        let key = key.into();
+        self.index.get(locale).is_some_and(|index| index.get(&key.path()).is_some())
+    }
+
+    /// Get the flattened, dot-delimited paths of all messages (and other values, such as numbers)
+    /// for `locale`, in sorted order.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// assert!(dict.keys("en").unwrap().contains(&String::from("custom.greeting")));
+    /// ```
+    pub fn keys(&self, locale: &str) -> err::Result<Vec<String>> {
+        let index = self
+            .index
+            .get(locale)
+            .ok_or_else(|| err::Error::UnknownLocale(String::from(locale).into_boxed_str()))?;
+        Ok(index
+            .entries()
+            .into_iter()
+            .filter(|(_, node)| matches!(node, index::Node::Message(_) | index::Node::Value(_)))
+            .map(|(path, _)| path.to_string())
+            .collect())
+    }
+
+    /// Get the raw message or subtree at `key` for `locale`, as written.
+    ///
+    /// `key` can be a dot-delimited `&str` or a `&[&str]` path.
+    ///
+    /// The subtree is rebuilt from the message index (for translation files and catalogs alike),
+    /// so it is returned by value.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// assert!(dict.lookup("en", "messages").unwrap().is_object());
+    /// ```
+    pub fn lookup<'a, K: Into<Key<'a>>>(&self, locale: &str, key: K) -> Option<serde_json::Value> {
+        self.index.get(locale)?.tree(&key.into().path())
+    }
+
+    /// Write the messages of all locales as a compact binary catalog, to be loaded with
+    /// `Config::with_catalog`.
+    ///
+    /// Catalogs keep the message syntax and the default locale, but not the other configuration
+    /// (plural rules, missing variable handling).
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// let path = std::env::temp_dir().join("loon-doctest.bin");
+    /// dict.write_catalog(std::fs::File::create(&path).unwrap()).unwrap();
+    /// let dict = Config::default().with_catalog(&path).finish().unwrap();
+    /// assert_eq!(dict.translate("custom.greeting", Var("name", "Jacob")).unwrap(), "Hello, Jacob!!!");
+    /// ```
+    pub fn write_catalog<W: std::io::Write>(&self, out: W) -> err::Result<()> {
+        catalog::write(self, out)
+    }
+
+    /// Get the raw message at `key` for `locale`, from the flattened message index.
+    ///
+    /// `key` can be a dot-delimited `&str` (looked up without allocating) or a `&[&str]` path.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::prelude::*;
+    /// let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+    /// assert_eq!(dict.message("en", "custom.greeting"), Some("Hello, {name}!!!"));
+    /// assert_eq!(dict.message("en", "messages"), None);
+    /// ```
+    pub fn message<'a, K: Into<Key<'a>>>(&self, locale: &str, key: K) -> Option<&str> {
+        let index = self.index.get(locale)?;
+        index.message(&key.into().path()).map(|message| message.text)
+    }
+
     /// Shortcut for `translate`.
     ///
     /// `key` can be a dot-delimited `&str` or a `&[&str]` path.
     ///
-    /// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Locale`, or `DefaultKey` (or up
-    /// to a `4-tuple` of these items).
+    /// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Ordinal`, `Select`, `Locale`,
+    /// `DefaultKey`, or `MissingVar` (or up to a `4-tuple` of these items).
     ///
     /// Examples:
     /// ```rust
@@ -253,14 +827,159 @@ impl Dictionary {
     ) -> err::Result<String> {
         self.translate(key, opts)
     }
-}
 
-static CONFIG: OnceCell<Config> = OnceCell::new();
+    /// Localize a date or time, ruby-i18n style.
+    ///
+    /// `format` is the name of a format in `date.formats` (for dates) or `time.formats` (for
+    /// dates with a time), e.g. `short` or `long`, or a custom `strftime` pattern such as
+    /// `%d.%m.%Y`. Names for `%a`, `%A`, `%b`, `%B` and `%p` come from `date.abbr_day_names`,
+    /// `date.day_names`, `date.abbr_month_names`, `date.month_names` and `time.am` / `time.pm`.
+    ///
+    /// `value` can be a `DateTime` or a `std::time::SystemTime` (or `chrono` dates and times with
+    /// the `chrono` feature), and `opts` is used for the `locale`.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::{prelude::*, DateTime};
+    /// let dict = Config::default().with_path_pattern("examples/rails/*.yml").finish().unwrap();
+    /// let date = DateTime::date(2020, 3, 4);
+    /// assert_eq!(dict.localize(date, "long", None).unwrap(), "March 4, 2020");
+    /// assert_eq!(dict.localize(date, "%a, %d.%m.", Locale("de")).unwrap(), "Mi, 04.03.");
+    /// ```
+    pub fn localize<'a, D: Into<DateTime>, I: Into<Opts<'a>>>(
+        &self,
+        value: D,
+        format: &str,
+        opts: I,
+    ) -> err::Result<String> {
+        let value = value.into();
+        let opts = opts.into();
+
+        let locale = opts.locale.unwrap_or_else(|| &self.default_locale);
+
+        let index = self
+            .index
+            .get(locale)
+            .ok_or_else(|| err::Error::UnknownLocale(String::from(locale).into_boxed_str()))?;
+
+        self.format_date(index, &value, format)
+    }
+
+    /// Shortcut for `localize`.
+    pub fn l<'a, D: Into<DateTime>, I: Into<Opts<'a>>>(
+        &self,
+        value: D,
+        format: &str,
+        opts: I,
+    ) -> err::Result<String> {
+        self.localize(value, format, opts)
+    }
+
+    /// Localize `value` with the catalog of a locale.
+    fn format_date(
+        &self,
+        index: &index::Index,
+        value: &DateTime,
+        format: &str,
+    ) -> err::Result<String> {
+        let pattern = if format.contains('%') {
+            format
+        } else {
+            let kind = if value.has_time() { "time" } else { "date" };
+            let key = format!("{}.formats.{}", kind, format);
+            match index.message(&key) {
+                Some(message) => message.text,
+                None => return Err(err::Error::UnknownKey(key.into_boxed_str())),
+            }
+        };
+
+        // Names are lists (`date.day_names.0`) or single messages (`time.am`)
+        let name = |key: &str, idx: usize| {
+            let item = format!("{}.{}", key, idx);
+            index
+                .message(&item)
+                .or_else(|| index.message(key))
+                .map(|message| String::from(message.text))
+                .ok_or_else(|| err::Error::UnknownKey(item.into_boxed_str()))
+        };
+
+        date::format(pattern, value, &name)
+    }
+
+    /// Format a relative time, e.g. "3 minutes ago", "in 2 days" or "yesterday".
+    ///
+    /// Uses the pluralized `relative_time.<unit>.past` / `.future` messages (with a `count`
+    /// variable) and the `relative_time.<unit>.-1` / `.0` / `.1` phrases of the locale, where
+    /// `<unit>` is a CLDR field such as `day`, `day-short` or `day-narrow`. English is bundled.
+    ///
+    /// `opts` is used for the `locale`.
+    ///
+    /// Examples:
+    /// ```rust
+    /// use loon::{prelude::*, RelativeNumeric, RelativeStyle, RelativeTime};
+    /// let dict = Dictionary::default();
+    /// let time = RelativeTime::seconds_from_now(-180);
+    /// assert_eq!(dict.relative_time(time, None).unwrap(), "3 minutes ago");
+    /// let time = time.with_style(RelativeStyle::Short);
+    /// assert_eq!(dict.relative_time(time, None).unwrap(), "3 min. ago");
+    /// let time = RelativeTime::seconds_from_now(86_400).with_numeric(RelativeNumeric::Auto);
+    /// assert_eq!(dict.relative_time(time, None).unwrap(), "tomorrow");
+    /// ```
+    pub fn relative_time<'a, I: Into<Opts<'a>>>(
+        &self,
+        time: RelativeTime,
+        opts: I,
+    ) -> err::Result<String> {
+        let opts = opts.into();
+        let locale = opts.locale.unwrap_or_else(|| &self.default_locale);
+        let (unit, value) = time.unit();
+        let fields = relative::fields(unit, time.style());
+
+        if time.numeric() == RelativeNumeric::Auto && (-1..=1).contains(&value) {
+            let index = value.to_string();
+            for field in &fields {
+                let key = ["relative_time", field, &index];
+                if let Some(phrase) = self.message(locale, &key[..]) {
+                    return Ok(phrase.into());
+                }
+            }
+            if plural::language(locale) == "en" {
+                if let Some(phrase) = relative::english_phrase(unit, time.style(), value) {
+                    return Ok(phrase.into());
+                }
+            }
+        }
+
+        let direction = if value < 0 { "past" } else { "future" };
+        let count = value.unsigned_abs();
+        for field in &fields {
+            let key = ["relative_time", field, direction];
+            if self.has_key(locale, &key[..]) {
+                return self.translate(&key[..], Opts::default().locale(locale).count(count));
+            }
+        }
+
+        if plural::language(locale) == "en" {
+            let one = self.cardinal(locale, &count.into()) == "one";
+            let pattern = relative::english_pattern(unit, time.style(), value < 0, one);
+            let count = number::format(locale, &count.into(), NumberStyle::Integer);
+            return Ok(pattern.replace("{0}", &count));
+        }
+
+        Err(err::Error::UnknownKey(
+            format!("relative_time.{}.{}", unit.as_str(), direction).into_boxed_str(),
+        ))
+    }
+}
 
 /// Sets the `Config` to use for the global `translate` call.
 ///
-/// `config` can be a `Config` object, or `DefaultLocale`, `PathPattern`, or `LocalizedPath` (or up
-/// to a `4-tuple` of these items).
+/// `config` can be a `Config` object, or `DefaultLocale`, `PathPattern`, `LocalizedPath`, or
+/// `PluralRule` (or up to a `4-tuple` of these items).
+///
+/// The global `Dictionary` is loaded from it on first use (call `reload` to load it right away and
+/// get any error). Can be called again to switch configurations: the current dictionary is dropped,
+/// and the new configuration is loaded on the next global call.
 ///
 /// Examples:
 /// ```rust
@@ -270,15 +989,54 @@ static CONFIG: OnceCell<Config> = OnceCell::new();
 /// loon::set_config((PathPattern("examples/locales/*.yml"), DefaultLocale("en")));
 /// ```
 pub fn set_config<I: Into<Config>>(config: I) -> err::Result<()> {
-    Ok(CONFIG.set(config.into()).map_err(|_| err::custom("`CONFIG` already set"))?)
+    global::set_config(config.into());
+    Ok(())
 }
 
-/// Get the translated message, using the global configuration.
+/// Reloads the global `Dictionary` from its configuration (e.g. after translation files change).
+///
+/// The new dictionary replaces the old one atomically: calls in progress finish with the old one.
+/// If loading fails, the error is returned and the current dictionary is kept. If the first load
+/// (on the first global call) failed, calls return that error until a `reload` succeeds or
+/// `set_config` is called.
+///
+/// Examples:
+/// ```rust
+/// use loon::prelude::*;
+/// loon::set_config(PathPattern("examples/locales/*.yml")).unwrap();
+/// loon::reload().unwrap();
+/// assert_eq!(t("greeting", None).unwrap(), "Hello, World!");
+/// ```
+pub fn reload() -> err::Result<()> {
+    global::reload()
+}
+
+/// Replaces the global `Dictionary` with `dict` (e.g. one built from a catalog or database).
+///
+/// A later `reload` rebuilds the dictionary from the global configuration. A `reload` (or first
+/// load) already in progress finishes first, so `dict` is not overwritten by it.
+///
+/// Examples:
+/// ```rust
+/// use loon::prelude::*;
+/// let dict = Config::default()
+///     .with_path_pattern("examples/locales/*.yml")
+///     .with_default_locale("de")
+///     .finish()
+///     .unwrap();
+/// loon::set_dictionary(dict);
+/// assert_eq!(t("greeting", None).unwrap(), "Hallo Welt!");
+/// ```
+pub fn set_dictionary(dict: Dictionary) {
+    global::replace(dict)
+}
+
+/// Get the translated message, using the global configuration.
 ///
 /// `key` can be a dot-delimited `&str` or a `&[&str]` path.
 ///
-/// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Locale`, or `DefaultKey` (or up
-/// to a `4-tuple` of these items).
+/// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Ordinal`, `Select`, `Locale`, or
+/// `DefaultKey` (or up to a `4-tuple` of these items).
 ///
 /// Examples:
 /// ```rust
@@ -289,18 +1047,47 @@ pub fn set_config<I: Into<Config>>(config: I) -> err::Result<()> {
 /// let _ = translate("greeting", (Locale("de"), (DefaultKey("missing.message"))));
 /// ```
 pub fn translate<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(key: K, opts: I) -> err::Result<String> {
-    static DICTIONARY_RESULT: Lazy<err::Result<Dictionary>> =
-        Lazy::new(|| CONFIG.get_or_init(Config::global).clone().finish());
+    global::with(|dict| dict.translate(key, opts))
+}
 
-    DICTIONARY_RESULT.as_ref().map_err(err::custom).and_then(|dict| dict.translate(key, opts))
+/// Write the translated message to `out`, using the global configuration.
+///
+/// See `Dictionary::translate_to`.
+pub fn translate_to<'a, W, K, I>(out: &mut W, key: K, opts: I) -> err::Result<()>
+where
+    W: fmt::Write,
+    K: Into<Key<'a>>,
+    I: Into<Opts<'a>>,
+{
+    global::with(|dict| dict.translate_to(out, key, opts))
+}
+
+/// Get the translated message as HTML, using the global configuration.
+///
+/// See `Dictionary::translate_html`.
+pub fn translate_html<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(
+    key: K,
+    opts: I,
+) -> err::Result<SafeHtml> {
+    global::with(|dict| dict.translate_html(key, opts))
+}
+
+/// Get the translated message as rich text, using the global configuration.
+///
+/// See `Dictionary::translate_rich`.
+pub fn translate_rich<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(
+    key: K,
+    opts: I,
+) -> err::Result<Vec<RichSegment>> {
+    global::with(|dict| dict.translate_rich(key, opts))
 }
 
 /// Shortcut for `translate`.
 ///
 /// `key` can be a dot-delimited `&str` or a `&[&str]` path.
 ///
-/// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Locale`, or `DefaultKey` (or up
-/// to a `4-tuple` of these items).
+/// `opts` can be an `Opts` object, `None`, or `Var`, `Count`, `Ordinal`, `Select`, `Locale`, or
+/// `DefaultKey` (or up to a `4-tuple` of these items).
 ///
 /// Examples:
 /// ```rust
@@ -314,13 +1101,52 @@ pub fn t<'a, K: Into<Key<'a>>, I: Into<Opts<'a>>>(key: K, opts: I) -> err::Resul
     translate(key, opts)
 }
 
+/// Localize a date or time, using the global configuration.
+///
+/// `format` is a named format (e.g. `short`) or a `strftime` pattern; see
+/// `Dictionary::localize`.
+///
+/// Examples:
+/// ```rust
+/// use loon::{prelude::*, DateTime};
+/// let _ = loon::localize(DateTime::date(2020, 3, 4), "short", None);
+/// let _ = loon::localize(std::time::SystemTime::now(), "%H:%M", Locale("de"));
+/// ```
+pub fn localize<'a, D: Into<DateTime>, I: Into<Opts<'a>>>(
+    value: D,
+    format: &str,
+    opts: I,
+) -> err::Result<String> {
+    global::with(|dict| dict.localize(value, format, opts))
+}
+
+/// Shortcut for `localize`.
+pub fn l<'a, D: Into<DateTime>, I: Into<Opts<'a>>>(
+    value: D,
+    format: &str,
+    opts: I,
+) -> err::Result<String> {
+    localize(value, format, opts)
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::prelude::*;
+    use crate::{
+        prelude::*, DateTime, Decimal, Money, PluralOperands, RelativeNumeric, RelativeStyle,
+        RelativeTime, RichSegment, TagRenderer, Value,
+    };
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    /// Tests that use the global dictionary take this lock, so they don't replace it for each other.
+    fn global() -> MutexGuard<'static, ()> {
+        static GLOBAL: Mutex<()> = Mutex::new(());
+        GLOBAL.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 
     #[test]
     fn it_works() {
+        let _global = global();
         crate::set_config(PathPattern("examples/locales/*.yml")).unwrap();
 
         assert_eq!(t(&["greeting"], None).unwrap(), String::from("Hello, World!"));
@@ -363,4 +1189,713 @@ mod tests {
             String::from("Hello, you. Your message is: \"a very nested message\"")
         );
     }
+
+    #[test]
+    fn reloads() {
+        let _global = global();
+        crate::set_config(PathPattern("examples/locales/*.yml")).unwrap();
+
+        // The global dictionary is shared with other threads and can be replaced.
+        let dict = Config::default()
+            .with_path_pattern("examples/locales/*.yml")
+            .with_default_locale("de")
+            .finish()
+            .unwrap();
+        crate::set_dictionary(dict);
+        assert_eq!(t("greeting", None).unwrap(), "Hallo Welt!");
+        let other = std::thread::spawn(|| t("greeting", None).unwrap());
+        assert_eq!(other.join().unwrap(), "Hallo Welt!");
+
+        crate::reload().unwrap();
+        assert_eq!(t("greeting", None).unwrap(), "Hello, World!");
+
+        // A new configuration is loaded on first use, and a failed load is kept until it is retried
+        let invalid = LocalizedPath("xx", "examples/locales/invalid/xx.yml");
+        let config =
+            Config::from((PathPattern("examples/locales/*.yml"), invalid)).with_strict(true);
+        crate::set_config(config).unwrap();
+        let err = t("greeting", None).unwrap_err().to_string();
+        assert!(t("greeting", None).unwrap_err().to_string().contains(&err));
+        assert!(crate::reload().is_err());
+
+        crate::set_config(PathPattern("examples/locales/*.yml")).unwrap();
+        assert_eq!(t("greeting", None).unwrap(), "Hello, World!");
+    }
+
+    #[test]
+    fn introspection() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        assert_eq!(dict.locales(), vec!["de", "en", "ru"]);
+
+        assert!(dict.has_key("en", "a.very.nested"));
+        assert!(dict.has_key("en", &["messages", "one"]));
+        assert!(!dict.has_key("de", "messages"));
+        assert!(!dict.has_key("fr", "greeting"));
+
+        assert_eq!(
+            dict.keys("de").unwrap(),
+            vec![
+                "greeting",
+                "numbers.price",
+                "numbers.summary",
+                "relative_time.day.-1",
+                "relative_time.day.0",
+                "relative_time.day.1",
+                "relative_time.day.future.one",
+                "relative_time.day.future.other",
+                "relative_time.day.past.one",
+                "relative_time.day.past.other",
+            ]
+        );
+
+        let keys = dict.keys("en").unwrap();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(keys.contains(&String::from("a.very.nested.message")));
+        assert!(keys.contains(&String::from("messages.zero")));
+        assert!(!keys.contains(&String::from("messages")));
+        assert!(dict.keys("fr").is_err());
+
+        assert_eq!(dict.lookup("de", "greeting"), Some(serde_json::json!("Hallo Welt!")));
+        assert_eq!(dict.lookup("en", "messages").map(|x| x.as_object().unwrap().len()), Some(3));
+        assert!(dict.lookup("en", "missing.nothing").is_none());
+        assert_eq!(
+            dict.lookup("de", "relative_time.day.future"),
+            Some(serde_json::json!({"one": "in {count} Tag", "other": "in {count} Tagen"}))
+        );
+    }
+
+    #[test]
+    fn cldr_plurals() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let ru = |count: Decimal| dict.translate("messages", (Locale("ru"), Count(count))).unwrap();
+
+        assert_eq!(ru(1.into()), "У вас 1 сообщение.");
+        assert_eq!(ru(3.into()), "У вас 3 сообщения.");
+        assert_eq!(ru(11.into()), "У вас 11 сообщений.");
+        assert_eq!(ru(21.into()), "У вас 21 сообщение.");
+        assert_eq!(ru(0.into()), "У вас 0 сообщений.");
+
+        assert_eq!(dict.translate("messages", Count(0)).unwrap(), "You have no messages.");
+        assert_eq!(dict.translate("greeting", Count(2)).unwrap(), "Hello, World!");
+
+        assert_eq!(dict.translate("messages", Count(1.5)).unwrap(), "You have 1.5 messages.");
+        assert_eq!(
+            dict.translate("messages", Count("1.0".parse::<Decimal>().unwrap())).unwrap(),
+            "You have 1.0 messages."
+        );
+        assert_eq!(
+            dict.translate("messages", Count(10_000_000_000_u64)).unwrap(),
+            "You have 10000000000 messages."
+        );
+        assert_eq!(ru(Decimal::new(15, 1)), "У вас 1.5 сообщения.");
+    }
+
+    #[test]
+    fn ordinals() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let en = |count| dict.translate("place", Ordinal(count)).unwrap();
+
+        assert_eq!(en(1), "1st place");
+        assert_eq!(en(22), "22nd place");
+        assert_eq!(en(13), "13th place");
+        assert_eq!(en(103), "103rd place");
+    }
+
+    #[test]
+    fn select() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let invited = |gender, count| {
+            dict.translate("invited", (Var("name", "Sam"), Select("gender", gender), Count(count)))
+                .unwrap()
+        };
+
+        assert_eq!(invited("female", 1), "Sam invited you and one other person.");
+        assert_eq!(invited("male", 3), "Sam invited you and 3 other people. He is waiting.");
+        assert_eq!(invited("unknown", 1), "Sam invited you. They are waiting.");
+        assert_eq!(invited("other", 2), "Sam invited you. They are waiting.");
+
+        assert_eq!(
+            dict.translate("greeting", Opts::default().select("gender", "female")).unwrap(),
+            "Hello, World!"
+        );
+    }
+
+    #[test]
+    fn lists() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let names = ["Alice", "Bob", "Carol"];
+
+        assert_eq!(
+            dict.translate("custom.greeting", List("name", names.to_vec(), ListType::Conjunction))
+                .unwrap(),
+            "Hello, Alice, Bob, and Carol!!!"
+        );
+        assert_eq!(
+            dict.translate(
+                "custom.greeting",
+                Opts::default().list("name", names.iter().copied(), ListType::Disjunction)
+            )
+            .unwrap(),
+            "Hello, Alice, Bob, or Carol!!!"
+        );
+    }
+
+    #[test]
+    fn interpolation_syntax() {
+        let dict = Config::from((PathPattern("examples/rails/*.yml"), Interpolation::Percent))
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            dict.translate("greeting", Var("name", "Jacob")).unwrap(),
+            "Hello, Jacob! Save 100% on {things} and %{escaped}."
+        );
+        assert_eq!(dict.translate("messages", Count(3)).unwrap(), "You have 3 messages.");
+
+        // A syntax error fails the message that has it, or `finish` when strict
+        let invalid = Config::from(LocalizedPath("xx", "examples/locales/invalid/xx.yml"));
+        let dict = invalid.clone().with_default_locale("xx").finish().unwrap();
+        let err = dict.translate("greeting", Var("name", "Jacob")).unwrap_err();
+        assert!(err.to_string().contains("xx.greeting"));
+        let err = invalid.with_strict(true).finish().unwrap_err();
+        assert!(err.to_string().contains("xx.greeting"));
+    }
+
+    #[test]
+    fn missing_vars() {
+        let dict = Config::from((PathPattern("examples/locales/*.yml"), MissingVar::Keep))
+            .finish()
+            .unwrap();
+
+        assert_eq!(dict.translate("custom.greeting", None).unwrap(), "Hello, {name}!!!");
+        assert_eq!(
+            dict.translate("a.very.nested.message", Var("name", "Jacob")).unwrap(),
+            "Hello, Jacob. Your message is: {message}"
+        );
+        assert_eq!(
+            dict.translate("custom.greeting", MissingVar::Marker("???".into())).unwrap(),
+            "Hello, ???!!!"
+        );
+        assert!(dict.translate("custom.greeting", MissingVar::Error).is_err());
+
+        let warned = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let dict = {
+            let warned = warned.clone();
+            Config::default()
+                .with_path_pattern("examples/locales/*.yml")
+                .with_missing_var(MissingVar::Empty)
+                .with_missing_var_hook(move |locale, key, var| {
+                    warned.lock().unwrap().push(format!("{}:{}:{}", locale, key, var))
+                })
+                .finish()
+                .unwrap()
+        };
+
+        assert_eq!(dict.translate("app.welcome", None).unwrap(), "Welcome to Loon, !");
+        assert_eq!(*warned.lock().unwrap(), vec!["en:app.welcome:name"]);
+
+        // Without a policy, calls with no variables keep the placeholders and others fail.
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+        assert_eq!(dict.translate("custom.greeting", None).unwrap(), "Hello, {name}!!!");
+        assert!(dict.translate("custom.greeting", Var("other", "x")).is_err());
+    }
+
+    #[test]
+    fn number_hints() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let opts = |locale| Opts::default().locale(locale).var("total", 1234.5).var("ratio", 0.5);
+        assert_eq!(
+            dict.translate("numbers.summary", opts("en")).unwrap(),
+            "Total: 1,234.5 (50% done)"
+        );
+        assert_eq!(
+            dict.translate("numbers.summary", opts("de")).unwrap(),
+            "Summe: 1.234,5 (50\u{a0}% fertig)"
+        );
+    }
+
+    #[test]
+    fn localize() {
+        let dict = Config::default().with_path_pattern("examples/rails/*.yml").finish().unwrap();
+        let date = DateTime::date(2020, 3, 4);
+        let time = date.and_time(18, 30, 0);
+
+        assert_eq!(dict.localize(date, "default", None).unwrap(), "2020-03-04");
+        assert_eq!(dict.localize(date, "short", None).unwrap(), "Mar 04");
+        assert_eq!(dict.l(date, "long", Locale("de")).unwrap(), "4. März 2020");
+        assert_eq!(dict.l(time, "short", None).unwrap(), "04 Mar 18:30");
+        assert_eq!(
+            dict.l(time, "long", Locale("de")).unwrap(),
+            "Mittwoch, 4. März 2020, 18:30 Uhr"
+        );
+        assert_eq!(dict.l(time, "%-I:%M %p", None).unwrap(), "6:30 PM");
+        assert!(dict.l(date, "full", None).is_err());
+        assert!(dict.l(time, "%p", Locale("de")).is_err());
+    }
+
+    #[test]
+    fn typed_vars() {
+        let dict = Config::from((PathPattern("examples/rails/*.yml"), Interpolation::Percent))
+            .finish()
+            .unwrap();
+        let opts = Opts::default()
+            .var("name", "Jacob")
+            .var("joined", DateTime::date(2020, 3, 4))
+            .list("friends", vec!["Sam", "Alex", "Kim"], ListType::Conjunction)
+            .var("points", 12_345_u64)
+            .var("plan", Value::translation("plans.pro"));
+        assert_eq!(
+            dict.translate("signup", opts).unwrap(),
+            "Jacob joined on 2020-03-04 (March 4, 2020) with Sam, Alex, and Kim, 12,345 points and \
+             a Pro plan."
+        );
+
+        let dict =
+            Config::from((PathPattern("examples/icu/*.yml"), MessageFormat::Icu)).finish().unwrap();
+        let photos =
+            |photos, shared| (Var("name", "Sam"), Var("photos", photos), Var("shared", shared));
+        assert_eq!(
+            dict.translate("photos", photos(1, true)).unwrap(),
+            "Sam added a photo to a shared album."
+        );
+        assert_eq!(
+            dict.translate("photos", photos(3, false)).unwrap(),
+            "Sam added 3 photos to their album."
+        );
+    }
+
+    #[test]
+    fn vars_from() {
+        #[derive(serde::Serialize)]
+        struct Stats {
+            posts: u32,
+        }
+
+        #[derive(serde::Serialize)]
+        struct User<'a> {
+            name: &'a str,
+            age: u8,
+            stats: Stats,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Profile<'a> {
+            user: User<'a>,
+            topics: Vec<&'a str>,
+        }
+
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+        let profile = Profile {
+            user: User { name: "Sam", age: 41, stats: Stats { posts: 1200 } },
+            topics: vec!["Rust", "i18n", "YAML"],
+        };
+
+        assert_eq!(
+            dict.translate("profile", Opts::default().vars_from(&profile).unwrap()).unwrap(),
+            "Sam (41) follows Rust, i18n, and YAML and has 1,200 posts."
+        );
+        assert!(Opts::default().vars_from(&["not", "a", "map"]).is_err());
+    }
+
+    #[test]
+    fn currency_hints() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let opts = |locale| Opts::default().locale(locale).var("price", Money::new(1234.5, "EUR"));
+        assert_eq!(dict.translate("numbers.price", opts("en")).unwrap(), "Price: €1,234.50");
+        assert_eq!(dict.translate("numbers.price", opts("de")).unwrap(), "Preis: 1.234,50\u{a0}€");
+        assert!(dict.translate("numbers.price", Var("price", 5)).is_err());
+    }
+
+    #[test]
+    fn relative_time() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+        let time = |seconds| RelativeTime::seconds_from_now(seconds);
+
+        assert_eq!(dict.relative_time(time(-1), None).unwrap(), "1 second ago");
+        assert_eq!(dict.relative_time(time(2 * 86_400), None).unwrap(), "in 2 days");
+        assert_eq!(
+            dict.relative_time(time(-3 * 3600).with_style(RelativeStyle::Narrow), None).unwrap(),
+            "3h ago"
+        );
+        assert_eq!(
+            dict.relative_time(time(0).with_numeric(RelativeNumeric::Auto), None).unwrap(),
+            "now"
+        );
+
+        let de = |time| dict.relative_time(time, Locale("de")).unwrap();
+        assert_eq!(de(time(-86_400)), "vor 1 Tag");
+        assert_eq!(de(time(3 * 86_400)), "in 3 Tagen");
+        assert_eq!(de(time(-86_400).with_numeric(RelativeNumeric::Auto)), "gestern");
+        assert_eq!(de(time(-2 * 86_400).with_numeric(RelativeNumeric::Auto)), "vor 2 Tagen");
+        assert_eq!(de(time(-86_400).with_style(RelativeStyle::Short)), "vor 1 Tag");
+        assert!(dict.relative_time(time(-60), Locale("de")).is_err());
+    }
+
+    #[test]
+    fn html() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+        let name = Var("name", "<script>alert('hi')</script>");
+
+        assert_eq!(
+            dict.translate_html("terms_html", (name, Locale("en"))).unwrap().to_string(),
+            "&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt;, please accept the \
+             <a href=\"/terms\">terms</a>."
+        );
+        assert_eq!(
+            dict.translate_html("footer.html", None).unwrap().as_str(),
+            "<small>&copy; Loon</small>"
+        );
+        assert_eq!(
+            dict.translate_html("custom.greeting", Var("name", "<b>Tom & Jerry</b>"))
+                .unwrap()
+                .as_str(),
+            "Hello, &lt;b&gt;Tom &amp; Jerry&lt;/b&gt;!!!"
+        );
+        assert_eq!(
+            dict.translate("terms_html", Var("name", "<b>")).unwrap(),
+            "<b>, please accept the <a href=\"/terms\">terms</a>."
+        );
+
+        // Each message used is trusted (or not) by its own key
+        let html = |key, opts: Opts| dict.translate_html(key, opts).unwrap().into_string();
+        assert_eq!(
+            html("markup.linked_html", Opts::default()),
+            "<p>Fish &amp; &lt;Chips&gt;</p> <small>&copy; Loon</small>"
+        );
+        assert_eq!(
+            html("markup.missing_html", Opts::default().default_key("markup.plain")),
+            "Fish &amp; &lt;Chips&gt;"
+        );
+        assert_eq!(
+            html("markup.missing", Opts::default().default_key("footer.html")),
+            "<small>&copy; Loon</small>"
+        );
+        assert_eq!(
+            html(
+                "markup.wrapped_html",
+                Opts::default().var("content", Value::translation("footer.html"))
+            ),
+            "<p><small>&copy; Loon</small></p>"
+        );
+        assert_eq!(
+            html(
+                "markup.wrapped_html",
+                Opts::default().var("content", Value::translation("markup.plain"))
+            ),
+            "<p>Fish &amp; &lt;Chips&gt;</p>"
+        );
+
+        // Missing-variable replacements are escaped
+        let marker = || MissingVar::Marker("<?>".into());
+        assert_eq!(html("markup.wrapped_html", marker().into()), "<p>&lt;?&gt;</p>");
+        assert_eq!(html("custom.greeting", marker().into()), "Hello, &lt;?&gt;!!!");
+    }
+
+    #[test]
+    fn translate_to() {
+        use std::{borrow::Cow, fmt::Write};
+
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let mut out = String::from("> ");
+        dict.translate_to(&mut out, "app.welcome", Var("name", "Jacob")).unwrap();
+        dict.translate_to(&mut out, "app.title", None).unwrap();
+        assert_eq!(out, "> Welcome to Loon, Jacob!Loon");
+        assert!(dict.translate_to(&mut out, "app.welcome", MissingVar::Error).is_err());
+
+        let lazy = dict.lazy("relative_time.day.past", (Count(2), Locale("de")));
+        assert_eq!(lazy.to_string(), "vor 2 Tagen");
+        assert!(write!(String::new(), "{}", dict.lazy("missing", None)).is_err());
+
+        assert!(dict.translate_cow("custom.greeting", MissingVar::Error).is_err());
+        assert!(matches!(dict.translate_cow("app.name", None), Ok(Cow::Borrowed("Loon"))));
+        assert!(matches!(dict.translate_cow("app.title", None), Ok(Cow::Owned(_))));
+        assert!(matches!(
+            dict.translate_cow("messages", Count(0)),
+            Ok(Cow::Borrowed("You have no messages."))
+        ));
+        assert!(matches!(
+            dict.translate_cow("app.welcome", Var("name", "Jacob")),
+            Ok(Cow::Owned(ref s)) if s == "Welcome to Loon, Jacob!"
+        ));
+    }
+
+    #[test]
+    fn catalogs() {
+        let write = |dict: &Dictionary, name: &str| {
+            let path =
+                std::env::temp_dir().join(format!("loon-{}-{}.bin", name, std::process::id()));
+            dict.write_catalog(std::fs::File::create(&path).unwrap()).unwrap();
+            path
+        };
+
+        let dict = Config::from((PathPattern("examples/locales/*.yml"), DefaultLocale("de")))
+            .finish()
+            .unwrap();
+        let path = write(&dict, "locales");
+        let loaded = Config::from(CatalogPath(&path)).finish().unwrap();
+        assert_eq!(loaded.locales(), dict.locales());
+        assert_eq!(loaded.keys("en").unwrap(), dict.keys("en").unwrap());
+        assert_eq!(loaded.translate("greeting", None).unwrap(), "Hallo Welt!");
+        for (key, opts) in [
+            ("messages", Opts::default().count(0).locale("en")),
+            (
+                "invited",
+                Opts::default().select("gender", "female").count(2).var("name", "Jo").locale("en"),
+            ),
+            ("app.title", Opts::default().locale("en")),
+            ("app.welcome", Opts::default().var("name", "Jacob").locale("en")),
+            ("numbers.summary", Opts::default().var("total", 1234.5).var("ratio", 0.5)),
+        ] {
+            assert_eq!(
+                loaded.translate(key, opts.clone()).unwrap(),
+                dict.translate(key, opts).unwrap()
+            );
+        }
+        assert!(loaded.has_key("en", "messages"));
+        assert_eq!(loaded.lookup("en", "messages"), dict.lookup("en", "messages"));
+
+        // Translation files override the catalog
+        let loaded =
+            Config::from((CatalogPath(&path), LocalizedPath("en", "examples/rails/en.yml")))
+                .finish()
+                .unwrap();
+        assert_eq!(loaded.default_locale, "de");
+        assert!(loaded.message("en", "greeting").unwrap().starts_with("Hello, %{name}!"));
+        assert_eq!(loaded.message("de", "greeting"), Some("Hallo Welt!"));
+
+        // Pseudo-locales of a default locale from the catalog
+        let loaded =
+            Config::from((CatalogPath(&path), PseudoLocale::accented().with_expansion(0.0)))
+                .finish()
+                .unwrap();
+        assert_eq!(loaded.translate("greeting", Locale("en-XA")).unwrap(), "[Ĥåļļö Ŵéļţ!]");
+
+        let dict = Config::from((PathPattern("examples/rails/*.yml"), Interpolation::Percent))
+            .finish()
+            .unwrap();
+        let loaded = Config::from(CatalogPath(write(&dict, "rails"))).finish().unwrap();
+        let months = dict.lookup("en", "date.month_names").unwrap();
+        assert_eq!(
+            months.as_array().map(|months| (months.len(), months[0].is_null())),
+            Some((13, true))
+        );
+        assert_eq!(loaded.lookup("en", "date.month_names"), Some(months));
+        assert_eq!(loaded.keys("en").unwrap(), dict.keys("en").unwrap());
+        let time = DateTime::date(2020, 3, 4).and_time(18, 30, 0);
+        assert_eq!(
+            loaded.l(time, "long", Locale("de")).unwrap(),
+            "Mittwoch, 4. März 2020, 18:30 Uhr"
+        );
+        assert_eq!(loaded.translate("messages", Count(3)).unwrap(), "You have 3 messages.");
+
+        let dict =
+            Config::from((PathPattern("examples/icu/*.yml"), MessageFormat::Icu)).finish().unwrap();
+        let loaded = Config::from(CatalogPath(write(&dict, "icu"))).finish().unwrap();
+        assert_eq!(loaded.translate("place", Ordinal(23)).unwrap(), "23rd place");
+
+        assert!(Config::from(CatalogPath("examples/locales/en.yml")).finish().is_err());
+    }
+
+    #[test]
+    fn rich() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        let segments =
+            dict.translate_rich("rich.invited", (Var("name", "<Sam>"), Count(3))).unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                RichSegment::Tag {
+                    name: "b".into(),
+                    children: vec![RichSegment::Var { name: "name".into(), value: "<Sam>".into() }]
+                },
+                RichSegment::Text(" invited ".into()),
+                RichSegment::Var { name: "count".into(), value: "3".into() },
+                RichSegment::Text(" people to ".into()),
+                RichSegment::Tag {
+                    name: "app".into(),
+                    children: vec![RichSegment::Text("Loon".into())]
+                },
+                RichSegment::Text(".".into()),
+            ]
+        );
+
+        let ansi = TagRenderer::new().tag("b", |s| format!("\x1b[1m{}\x1b[0m", s));
+        assert_eq!(ansi.render(&segments), "\x1b[1m<Sam>\x1b[0m invited 3 people to Loon.");
+
+        // Values are never parsed for markup
+        let name = "\u{e000}<i>\u{e001}</b>\u{e002}";
+        let segments = dict.translate_rich("rich.invited", (Var("name", name), Count(3))).unwrap();
+        assert_eq!(
+            segments[0],
+            RichSegment::Tag {
+                name: "b".into(),
+                children: vec![RichSegment::Var { name: "name".into(), value: name.into() }]
+            }
+        );
+
+        assert!(dict.translate_rich("rich.broken", None).is_err());
+        assert_eq!(
+            dict.translate("rich.terms", Var("name", "Jacob")).unwrap(),
+            "Jacob, read our <link>terms</link> and <b>privacy policy</b>."
+        );
+    }
+
+    #[test]
+    fn pseudo_locales() {
+        let dict = Config::default()
+            .with_path_pattern("examples/locales/*.yml")
+            .with_pseudo_locale(PseudoLocale::accented())
+            .with_pseudo_locale(PseudoLocale::bidi())
+            .finish()
+            .unwrap();
+
+        assert_eq!(dict.locales(), vec!["ar-XB", "de", "en", "en-XA", "ru"]);
+        assert_eq!(
+            dict.translate("messages", (Locale("en-XA"), Count(3))).unwrap(),
+            "[Ýöû ĥåṽé 3 ɱéššåĝéš. one tw]"
+        );
+        assert_eq!(
+            dict.translate("app.inbox", (Locale("en-XA"), Count(3))).unwrap(),
+            "[Ļööñ o]: [Ýöû ĥåṽé 3 ɱéššåĝéš. one tw]"
+        );
+        assert_eq!(
+            dict.translate(
+                "numbers.summary",
+                (Locale("en-XA"), Var("total", 1234), Var("ratio", 0.5))
+            )
+            .unwrap(),
+            "[Ţöţåļ: 1,234 (50% ðöñé) one t]"
+        );
+        assert_eq!(
+            dict.translate("custom.greeting", (Locale("ar-XB"), Var("name", "Jacob"))).unwrap(),
+            "\u{202e}Hello,\u{202c} Jacob\u{202e}!!!\u{202c}"
+        );
+        assert_eq!(
+            dict.translate_html("terms_html", (Locale("en-XA"), Var("name", "<b>")))
+                .unwrap()
+                .as_str(),
+            "[&lt;b&gt;, þļéåšé åççéþţ ţĥé <a href=\"/terms\">ţéŕɱš</a>. one two]"
+        );
+
+        let dict = Config::from((
+            PathPattern("examples/icu/*.yml"),
+            MessageFormat::Icu,
+            PseudoLocale::accented().with_expansion(0.0),
+        ))
+        .finish()
+        .unwrap();
+        assert_eq!(
+            dict.translate("invited", (Locale("en-XA"), Select("gender", "female"), Count(3)))
+                .unwrap(),
+            "[Šĥé îñṽîţéð ýöû åñð 2 öţĥéŕ þéöþļé.]"
+        );
+
+        // Date formats and names are not pseudo-localized
+        let dict = Config::from((
+            PathPattern("examples/rails/*.yml"),
+            Interpolation::Percent,
+            PseudoLocale::accented().with_expansion(0.0),
+        ))
+        .finish()
+        .unwrap();
+        let date = DateTime::date(2020, 3, 4).and_time(13, 45, 0);
+        assert_eq!(dict.localize(date, "long", Locale("en-XA")).unwrap(), "March 4, 2020 13:45");
+        assert_eq!(dict.localize(date, "%a %p", Locale("en-XA")).unwrap(), "Wed PM");
+        assert_eq!(
+            dict.translate("messages", (Locale("en-XA"), Count(3))).unwrap(),
+            "[Ýöû ĥåṽé 3 ɱéššåĝéš.]"
+        );
+    }
+
+    #[test]
+    fn links() {
+        let dict = Config::default().with_path_pattern("examples/locales/*.yml").finish().unwrap();
+
+        assert_eq!(dict.translate("app.title", None).unwrap(), "Loon");
+        assert_eq!(
+            dict.translate("app.welcome", Var("name", "Jacob")).unwrap(),
+            "Welcome to Loon, Jacob!"
+        );
+        assert_eq!(dict.translate("app.inbox", Count(2)).unwrap(), "Loon: You have 2 messages.");
+        assert_eq!(dict.translate("app.symbol", None).unwrap(), ":app.name");
+
+        // Links don't use the count, selects or `default_key` of the call
+        assert!(dict.translate("app.plural", Count(1)).is_err());
+        assert!(matches!(
+            dict.translate("app.broken", Opts::default().default_key("app.name")),
+            Err(crate::err::Error::UnknownKey(_))
+        ));
+
+        match dict.translate("app.cycle.a", None) {
+            Err(crate::err::Error::Link(e)) => assert!(e.contains("cycle")),
+            other => panic!("expected link error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn icu_message_format() {
+        let dict =
+            Config::from((PathPattern("examples/icu/*.yml"), MessageFormat::Icu)).finish().unwrap();
+
+        assert_eq!(dict.translate("greeting", Var("name", "Jacob")).unwrap(), "Hello, Jacob!");
+        assert_eq!(dict.translate("messages", Count(0)).unwrap(), "You have no messages.");
+        assert_eq!(dict.translate("messages", Count(1)).unwrap(), "You have one message.");
+        assert_eq!(dict.translate("messages", Count(2.5)).unwrap(), "You have 2.5 messages.");
+        assert_eq!(
+            dict.translate("invited", (Select("gender", "female"), Count(1))).unwrap(),
+            "She invited you and nobody else."
+        );
+        assert_eq!(
+            dict.translate("invited", (Var("gender", "x"), Count(3))).unwrap(),
+            "They invited you and 2 other people."
+        );
+        assert_eq!(dict.translate("place", Ordinal(23)).unwrap(), "23rd place");
+        assert_eq!(dict.translate("quoted", None).unwrap(), "It's {literally} braces");
+        assert_eq!(
+            dict.translate("app.welcome", (Var("name", "Jacob"), Count(1))).unwrap(),
+            "Welcome to Loon, Jacob! You have one message."
+        );
+        assert!(dict.translate("greeting", MissingVar::Error).is_err());
+
+        let err =
+            Config::from((LocalizedPath("xx", "examples/icu/invalid/xx.yml"), MessageFormat::Icu))
+                .with_strict(true)
+                .finish()
+                .unwrap_err();
+        assert!(err.to_string().contains("xx.broken"));
+    }
+
+    #[test]
+    fn custom_plural_rules() {
+        let dict = Config::default()
+            .with_path_pattern("examples/locales/*.yml")
+            .with_plural_rule("en", |ops| if ops.i < 5 && ops.v == 0 { "one" } else { "other" })
+            .finish()
+            .unwrap();
+
+        assert_eq!(dict.translate("messages", Count(3)).unwrap(), "You have one message.");
+        assert_eq!(dict.translate("messages", Count(5)).unwrap(), "You have 5 messages.");
+        assert_eq!(dict.translate("messages", Count(0)).unwrap(), "You have no messages.");
+
+        let dict = Config::from((
+            LocalizedPath("ru-RU", "examples/locales/ru.yml"),
+            PluralRule("ru", |_: &PluralOperands| "many"),
+        ))
+        .finish()
+        .unwrap();
+
+        assert_eq!(
+            dict.translate("messages", (Locale("ru-RU"), Count(1))).unwrap(),
+            "У вас 1 сообщений."
+        );
+    }
 }
@@ -0,0 +1,81 @@
+//! Links between translations.
+//!
+//! A message can contain `$t(app.name)` references that are replaced with the linked translation,
+//! or be a single reference that aliases another key. Linked messages are translated with the
+//! locale and variables of the call only.
+
+/// Maximum number of nested links followed for a single `translate` call.
+pub(crate) const MAX_DEPTH: usize = 8;
+
+/// A piece of a message that may contain `$t(key)` references
+#[derive(Debug, PartialEq)]
+pub(crate) enum Piece<'s> {
+    Text(&'s str),
+    Link(&'s str),
+}
+
+fn is_key(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// If `text` starts with a `$t(key)` reference, get the key and the length of the reference.
+pub(crate) fn reference(text: &str) -> Option<(&str, usize)> {
+    let rest = text.strip_prefix("$t(")?;
+    let end = rest.find(')')?;
+    let key = rest[..end].trim();
+    if is_key(key) {
+        Some((key, "$t(".len() + end + 1))
+    } else {
+        None
+    }
+}
+
+/// Split `message` into text and `$t(key)` references.
+pub(crate) fn pieces(message: &str) -> Vec<Piece<'_>> {
+    let mut out = Vec::new();
+    let mut rest = message;
+    let mut start = 0;
+
+    while let Some(idx) = rest[start..].find("$t(").map(|idx| start + idx) {
+        match reference(&rest[idx..]) {
+            Some((key, len)) => {
+                if idx > 0 {
+                    out.push(Piece::Text(&rest[..idx]));
+                }
+                out.push(Piece::Link(key));
+                rest = &rest[idx + len..];
+                start = 0;
+            }
+            None => start = idx + 1,
+        }
+    }
+
+    if !rest.is_empty() {
+        out.push(Piece::Text(rest));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{pieces, Piece::*};
+
+    #[test]
+    fn parses() {
+        assert_eq!(
+            pieces("Welcome to $t(app.name), $t( user ) $t(bad key) $t("),
+            vec![
+                Text("Welcome to "),
+                Link("app.name"),
+                Text(", "),
+                Link("user"),
+                Text(" $t(bad key) $t("),
+            ]
+        );
+        assert_eq!(pieces("$t(a)$t(b)"), vec![Link("a"), Link("b")]);
+        // ruby-i18n symbols are not links: `:ok` is text
+        assert_eq!(pieces(":app.name"), vec![Text(":app.name")]);
+    }
+}
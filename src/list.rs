@@ -0,0 +1,138 @@
+//! Locale-aware list formatting, using CLDR list patterns.
+
+use super::plural::language;
+
+/// Kind of list to format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListType {
+    /// "A, B, and C"
+    #[default]
+    Conjunction,
+    /// "A, B, or C"
+    Disjunction,
+}
+
+/// Separators between list items: `middle` between all but the last two items, `end` between
+/// the last two items, and `two` between the items of a two-item list.
+struct Patterns {
+    middle: &'static str,
+    end: &'static str,
+    two: &'static str,
+}
+
+const fn simple(sep: &'static str) -> Patterns {
+    Patterns { middle: ", ", end: sep, two: sep }
+}
+
+fn patterns(locale: &str, list_type: ListType) -> Patterns {
+    use ListType::*;
+
+    let locale = locale.to_ascii_lowercase().replace('_', "-");
+
+    match (language(&locale).as_str(), list_type) {
+        ("en", Conjunction)
+            if ["en-gb", "en-au", "en-ie", "en-nz"].iter().any(|x| locale.starts_with(x)) =>
+        {
+            simple(" and ")
+        }
+        ("en", Disjunction)
+            if ["en-gb", "en-au", "en-ie", "en-nz"].iter().any(|x| locale.starts_with(x)) =>
+        {
+            simple(" or ")
+        }
+        ("de", Conjunction) => simple(" und "),
+        ("de", Disjunction) => simple(" oder "),
+        ("fr", Conjunction) => simple(" et "),
+        ("fr", Disjunction) => simple(" ou "),
+        ("es", Conjunction) => simple(" y "),
+        ("es", Disjunction) => simple(" o "),
+        ("it", Conjunction) => simple(" e "),
+        ("it", Disjunction) => simple(" o "),
+        ("pt", Conjunction) => simple(" e "),
+        ("pt", Disjunction) => simple(" ou "),
+        ("nl", Conjunction) => simple(" en "),
+        ("nl", Disjunction) => simple(" of "),
+        ("sv", Conjunction) => simple(" och "),
+        ("da", Conjunction) | ("nb", Conjunction) | ("nn", Conjunction) | ("no", Conjunction) => {
+            simple(" og ")
+        }
+        ("sv", Disjunction)
+        | ("da", Disjunction)
+        | ("nb", Disjunction)
+        | ("nn", Disjunction)
+        | ("no", Disjunction) => simple(" eller "),
+        ("fi", Conjunction) => simple(" ja "),
+        ("fi", Disjunction) => simple(" tai "),
+        ("pl", Conjunction) => simple(" i "),
+        ("pl", Disjunction) => simple(" lub "),
+        ("ru", Conjunction) => simple(" и "),
+        ("ru", Disjunction) => simple(" или "),
+        ("uk", Conjunction) => simple(" і "),
+        ("uk", Disjunction) => simple(" або "),
+        ("cs", Conjunction) => simple(" a "),
+        ("cs", Disjunction) => simple(" nebo "),
+        ("tr", Conjunction) => simple(" ve "),
+        ("tr", Disjunction) => simple(" veya "),
+        ("el", Conjunction) => simple(" και "),
+        ("el", Disjunction) => simple(" ή "),
+        ("he", Conjunction) => simple(" ו"),
+        ("he", Disjunction) => simple(" או "),
+        ("hi", Conjunction) => Patterns { middle: ", ", end: ", और ", two: " और " },
+        ("hi", Disjunction) => simple(" या "),
+        ("ar", Conjunction) => Patterns { middle: " و", end: " و", two: " و" },
+        ("ar", Disjunction) => Patterns { middle: " أو ", end: " أو ", two: " أو " },
+        ("ja", Conjunction) => Patterns { middle: "、", end: "、", two: "、" },
+        ("ja", Disjunction) => Patterns { middle: "、", end: "、または", two: "または" },
+        ("zh", Conjunction) => Patterns { middle: "、", end: "和", two: "和" },
+        ("zh", Disjunction) => Patterns { middle: "、", end: "或", two: "或" },
+        ("ko", Conjunction) => simple(" 및 "),
+        ("ko", Disjunction) => simple(" 또는 "),
+        // English, also used for locales without patterns of their own
+        (_, Conjunction) => Patterns { middle: ", ", end: ", and ", two: " and " },
+        (_, Disjunction) => Patterns { middle: ", ", end: ", or ", two: " or " },
+    }
+}
+
+/// Join `items` with the list pattern for `locale` (or the English one, for other locales).
+pub(crate) fn format<S: AsRef<str>>(locale: &str, list_type: ListType, items: &[S]) -> String {
+    let patterns = patterns(locale, list_type);
+    let mut out = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push_str(match (items.len(), i) {
+                (2, _) => patterns.two,
+                (len, i) if i == len - 1 => patterns.end,
+                _ => patterns.middle,
+            });
+        }
+        out.push_str(item.as_ref());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{format, ListType::*};
+
+    #[test]
+    fn formats() {
+        let items = ["Alice", "Bob", "Carol"];
+
+        assert_eq!(format("en", Conjunction, &items), "Alice, Bob, and Carol");
+        assert_eq!(format("en-GB", Conjunction, &items), "Alice, Bob and Carol");
+        assert_eq!(format("en", Disjunction, &items[..2]), "Alice or Bob");
+        assert_eq!(format("de", Conjunction, &items), "Alice, Bob und Carol");
+        assert_eq!(format("fr_FR", Disjunction, &items), "Alice, Bob ou Carol");
+        assert_eq!(format("ja", Conjunction, &items), "Alice、Bob、Carol");
+        assert_eq!(format("en", Conjunction, &items[..1]), "Alice");
+        assert_eq!(format("en", Conjunction, &[] as &[&str]), "");
+
+        // Locales without patterns use the English ones
+        assert_eq!(format("tlh", Conjunction, &items), "Alice, Bob, and Carol");
+        assert_eq!(format("tlh", Disjunction, &items), "Alice, Bob, or Carol");
+        assert_eq!(format("tlh", Conjunction, &items[..2]), "Alice and Bob");
+    }
+}
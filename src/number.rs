@@ -0,0 +1,481 @@
+use super::{err, plural::language};
+use std::convert::TryFrom;
+
+/// The largest number of fraction digits a `Decimal` can hold.
+const MAX_SCALE: u32 = 38;
+
+/// A decimal number that remembers its visible fraction digits
+///
+/// Used for `count` values, so that e.g. `1` and `1.0` can select different plural forms.
+///
+/// Can be built from any primitive integer or float, parsed from a string, or created
+/// from a mantissa and scale:
+///
+/// ```rust
+/// use loon::Decimal;
+/// assert_eq!(Decimal::from(1234_u64).to_string(), "1234");
+/// assert_eq!(Decimal::from(1.5).to_string(), "1.5");
+/// assert_eq!("1.50".parse::<Decimal>().unwrap().to_string(), "1.50");
+/// assert_eq!(Decimal::new(-150, 2).to_string(), "-1.50");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Decimal {
+    negative: bool,
+    mantissa: u128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Create a `Decimal` equal to `mantissa / 10^scale`.
+    ///
+    /// Panics if `scale` is greater than 38.
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        assert!(scale <= MAX_SCALE, "`scale` must be at most {}", MAX_SCALE);
+        Self { negative: mantissa < 0, mantissa: mantissa.unsigned_abs(), scale }
+    }
+
+    /// Number of visible fraction digits, including trailing zeros.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// `true` if the value is zero (with any number of fraction digits).
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    /// `true` if the value is less than zero.
+    pub fn is_negative(&self) -> bool {
+        self.negative && self.mantissa != 0
+    }
+
+    /// The absolute integer part of the value.
+    pub(crate) fn integer_part(&self) -> u128 {
+        self.mantissa / 10u128.pow(self.scale)
+    }
+
+    /// The absolute visible fraction digits of the value, as an integer.
+    pub(crate) fn fraction_part(&self) -> u128 {
+        self.mantissa % 10u128.pow(self.scale)
+    }
+
+    /// Subtract the integer `n`, keeping the visible fraction digits.
+    pub(crate) fn sub_integer(&self, n: i64) -> Self {
+        if n == 0 {
+            return *self;
+        }
+        let abs = i128::try_from(self.mantissa).unwrap_or(i128::MAX);
+        let value = if self.negative { -abs } else { abs };
+        let shift = i128::from(n).saturating_mul(10i128.pow(self.scale));
+        Self::new(value.saturating_sub(shift), self.scale)
+    }
+
+    /// Round to at most `digits` fraction digits (half to even).
+    pub(crate) fn round(&self, digits: u32) -> Self {
+        if self.scale <= digits {
+            return *self;
+        }
+        let div = 10u128.pow(self.scale - digits);
+        let (quotient, remainder) = (self.mantissa / div, self.mantissa % div);
+        let half = div / 2;
+        let mantissa = if remainder > half || (remainder == half && quotient % 2 == 1) {
+            quotient + 1
+        } else {
+            quotient
+        };
+        Self { negative: self.negative, mantissa, scale: digits }
+    }
+
+    /// Remove trailing fraction zeros.
+    pub(crate) fn trim(&self) -> Self {
+        let mut out = *self;
+        while out.scale > 0 && out.mantissa % 10 == 0 {
+            out.mantissa /= 10;
+            out.scale -= 1;
+        }
+        out
+    }
+
+    /// Multiply by `10^exp`.
+    pub(crate) fn shift(&self, exp: i32) -> Self {
+        let mut out = *self;
+        if exp >= 0 {
+            let exp = exp as u32;
+            if out.scale >= exp {
+                out.scale -= exp;
+            } else {
+                let factor = 10u128.checked_pow(exp - out.scale).unwrap_or(u128::MAX);
+                out.mantissa = out.mantissa.saturating_mul(factor);
+                out.scale = 0;
+            }
+        } else {
+            let scale = out.scale + exp.unsigned_abs();
+            if scale > MAX_SCALE {
+                out.mantissa /= 10u128.checked_pow(scale - MAX_SCALE).unwrap_or(u128::MAX);
+                out.scale = MAX_SCALE;
+            } else {
+                out.scale = scale;
+            }
+        }
+        out
+    }
+
+    /// Round or pad to exactly `scale` fraction digits.
+    pub(crate) fn with_scale(&self, scale: u32) -> Self {
+        if self.scale >= scale {
+            return self.round(scale);
+        }
+        let factor = 10u128.pow(scale.min(MAX_SCALE) - self.scale);
+        Self {
+            negative: self.negative,
+            mantissa: self.mantissa.saturating_mul(factor),
+            scale: scale.min(MAX_SCALE),
+        }
+    }
+
+    /// Approximate the value as an `f64`.
+    pub fn to_f64(&self) -> f64 {
+        let abs = self.mantissa as f64 / 10f64.powi(self.scale as i32);
+        if self.negative {
+            -abs
+        } else {
+            abs
+        }
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_negative() {
+            f.write_str("-")?;
+        }
+        write!(f, "{}", self.integer_part())?;
+        if self.scale > 0 {
+            write!(f, ".{:0width$}", self.fraction_part(), width = self.scale as usize)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Decimal {
+    type Err = err::Error;
+
+    fn from_str(s: &str) -> err::Result<Self> {
+        let invalid = || err::custom(format!("Invalid decimal: {}", s));
+
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        let (int, frac) = match digits.find('.') {
+            Some(idx) => (&digits[..idx], &digits[idx + 1..]),
+            None => (digits, ""),
+        };
+
+        if int.is_empty()
+            || frac.len() > MAX_SCALE as usize
+            || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+            || (digits.contains('.') && frac.is_empty())
+        {
+            return Err(invalid());
+        }
+
+        let mut mantissa = 0u128;
+        for b in int.bytes().chain(frac.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(u128::from(b - b'0')))
+                .ok_or_else(invalid)?;
+        }
+
+        Ok(Self { negative, mantissa, scale: frac.len() as u32 })
+    }
+}
+
+macro_rules! decimal_from_int {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for Decimal {
+                fn from(t: $t) -> Self {
+                    Self::new(i128::from(t), 0)
+                }
+            }
+        )*
+    };
+}
+
+decimal_from_int!(i8 i16 i32 i64 i128 u8 u16 u32 u64);
+
+impl From<usize> for Decimal {
+    fn from(t: usize) -> Self {
+        Self { negative: false, mantissa: t as u128, scale: 0 }
+    }
+}
+
+impl From<isize> for Decimal {
+    fn from(t: isize) -> Self {
+        Self::new(t as i128, 0)
+    }
+}
+
+impl From<u128> for Decimal {
+    fn from(t: u128) -> Self {
+        Self { negative: false, mantissa: t, scale: 0 }
+    }
+}
+
+/// Uses the shortest representation that round-trips, so `1.5` has one fraction digit.
+///
+/// `NaN` becomes zero, and values too large for a `Decimal` are saturated. Fraction digits beyond
+/// 38 are truncated.
+impl From<f64> for Decimal {
+    fn from(t: f64) -> Self {
+        if t.is_nan() {
+            return Self::default();
+        }
+        let repr = t.abs().to_string();
+        let repr = match repr.find('.') {
+            Some(idx) if repr.len() - idx - 1 > MAX_SCALE as usize => {
+                &repr[..idx + 1 + MAX_SCALE as usize]
+            }
+            _ => &repr[..],
+        };
+        let abs =
+            repr.parse::<Self>().unwrap_or(Self { negative: false, mantissa: u128::MAX, scale: 0 });
+        Self { negative: t.is_sign_negative(), ..abs }
+    }
+}
+
+impl From<f32> for Decimal {
+    fn from(t: f32) -> Self {
+        // Go through the `f32` representation to keep its shortest form (e.g. `0.1`).
+        t.to_string().parse::<f64>().map(Self::from).unwrap_or_default()
+    }
+}
+
+/// How to format a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberStyle {
+    /// Grouped, with up to three fraction digits: "1,234,567.5" (the default)
+    #[default]
+    Decimal,
+    /// Grouped and rounded to an integer: "1,234,568"
+    Integer,
+    /// Multiplied by 100: "45%"
+    Percent,
+    /// Abbreviated: "1.2K", "3.4M"
+    Compact,
+}
+
+/// CLDR number symbols for a locale
+pub(crate) struct Symbols {
+    pub(crate) decimal: &'static str,
+    pub(crate) group: &'static str,
+    pub(crate) minus: &'static str,
+    /// Prefix and suffix for percentages
+    percent: (&'static str, &'static str),
+    /// Native digits, if the locale doesn't use `0-9`
+    digits: Option<[char; 10]>,
+    /// Size of the groups after the first (2 for Indian style "12,34,567")
+    secondary: usize,
+    /// Minimum number of digits in the highest group for grouping to be used
+    min_grouping: usize,
+}
+
+const LATN: Symbols = Symbols {
+    decimal: ".",
+    group: ",",
+    minus: "-",
+    percent: ("", "%"),
+    digits: None,
+    secondary: 3,
+    min_grouping: 1,
+};
+
+const COMMA_DOT: Symbols = Symbols { decimal: ",", group: ".", ..LATN };
+
+const COMMA_SPACE: Symbols =
+    Symbols { decimal: ",", group: "\u{a0}", percent: ("", "\u{a0}%"), ..LATN };
+
+const ARAB: [char; 10] = ['٠', '١', '٢', '٣', '٤', '٥', '٦', '٧', '٨', '٩'];
+const ARABEXT: [char; 10] = ['۰', '۱', '۲', '۳', '۴', '۵', '۶', '۷', '۸', '۹'];
+const BENG: [char; 10] = ['০', '১', '২', '৩', '৪', '৫', '৬', '৭', '৮', '৯'];
+const DEVA: [char; 10] = ['०', '१', '२', '३', '४', '५', '६', '७', '८', '९'];
+
+pub(crate) fn symbols(locale: &str) -> Symbols {
+    let locale = locale.to_ascii_lowercase().replace('_', "-");
+
+    match language(&locale).as_str() {
+        "de" if locale.starts_with("de-ch") || locale.starts_with("de-li") => {
+            Symbols { group: "’", ..LATN }
+        }
+        "de" if locale.starts_with("de-at") => Symbols { group: "\u{a0}", ..COMMA_SPACE },
+        "de" | "da" => Symbols { percent: ("", "\u{a0}%"), ..COMMA_DOT },
+        "es" => Symbols { percent: ("", "\u{a0}%"), min_grouping: 2, ..COMMA_DOT },
+        "it" | "nl" | "pt" | "id" | "el" | "ro" | "hr" | "sl" | "sr" => COMMA_DOT,
+        "tr" => Symbols { percent: ("%", ""), ..COMMA_DOT },
+        "pt-pt" => Symbols { percent: ("", "%"), min_grouping: 2, ..COMMA_SPACE },
+        "pl" => Symbols { percent: ("", "%"), min_grouping: 2, ..COMMA_SPACE },
+        "fr" => Symbols { group: "\u{202f}", percent: ("", "\u{202f}%"), ..COMMA_SPACE },
+        "sv" | "fi" | "nb" | "nn" | "no" => Symbols { minus: "−", ..COMMA_SPACE },
+        "ru" | "uk" | "be" | "cs" | "sk" | "bg" | "hu" | "lt" | "lv" | "et" => COMMA_SPACE,
+        "hi" => Symbols { secondary: 2, ..LATN },
+        "bn" => Symbols { digits: Some(BENG), secondary: 2, ..LATN },
+        "mr" => Symbols { digits: Some(DEVA), secondary: 2, ..LATN },
+        "ar" => Symbols {
+            decimal: "٫",
+            group: "٬",
+            minus: "\u{61c}-",
+            percent: ("", "٪\u{61c}"),
+            digits: Some(ARAB),
+            ..LATN
+        },
+        "fa" => Symbols {
+            decimal: "٫",
+            group: "٬",
+            minus: "\u{200e}−",
+            percent: ("", "٪"),
+            digits: Some(ARABEXT),
+            ..LATN
+        },
+        _ => LATN,
+    }
+}
+
+/// Compact units (power of ten and suffix), and the separator before the suffix
+fn compact_units(locale: &str) -> (&'static [(u32, &'static str)], &'static str) {
+    match language(locale).as_str() {
+        "de" => (&[(6, "Mio."), (9, "Mrd."), (12, "Bio.")], "\u{a0}"),
+        "fr" => (&[(3, "k"), (6, "M"), (9, "Md"), (12, "Bn")], "\u{a0}"),
+        "es" => (&[(3, "mil"), (6, "M"), (12, "B")], "\u{a0}"),
+        "it" => (&[(6, "Mln"), (9, "Mrd"), (12, "Bln")], "\u{a0}"),
+        "pt" | "pt-pt" => (&[(3, "mil"), (6, "mi"), (9, "bi"), (12, "tri")], "\u{a0}"),
+        "nl" => (&[(3, "K"), (6, "mln."), (9, "mld."), (12, "bln.")], "\u{a0}"),
+        "ru" => (&[(3, "тыс."), (6, "млн"), (9, "млрд"), (12, "трлн")], "\u{a0}"),
+        "ja" => (&[(4, "万"), (8, "億"), (12, "兆")], ""),
+        "zh" => (&[(4, "万"), (8, "亿"), (12, "万亿")], ""),
+        "ko" => (&[(3, "천"), (4, "만"), (8, "억"), (12, "조")], ""),
+        _ => (&[(3, "K"), (6, "M"), (9, "B"), (12, "T")], ""),
+    }
+}
+
+/// Write the integer and fraction digits of `value` (without sign) with the `symbols`.
+pub(crate) fn digits(value: &Decimal, symbols: &Symbols) -> String {
+    let abs = Decimal { negative: false, ..*value }.to_string();
+    let (int, frac) = match abs.find('.') {
+        Some(idx) => (&abs[..idx], Some(&abs[idx + 1..])),
+        None => (&abs[..], None),
+    };
+
+    let mut groups = Vec::new();
+    let mut end = int.len();
+    if end >= 3 + symbols.min_grouping {
+        let mut size = 3;
+        while end > size {
+            groups.push(&int[end - size..end]);
+            end -= size;
+            size = symbols.secondary;
+        }
+    }
+    groups.push(&int[..end]);
+    groups.reverse();
+
+    let mut out = groups.join(symbols.group);
+    if let Some(frac) = frac {
+        out.push_str(symbols.decimal);
+        out.push_str(frac);
+    }
+
+    match symbols.digits {
+        Some(native) => {
+            out.chars().map(|c| c.to_digit(10).map(|d| native[d as usize]).unwrap_or(c)).collect()
+        }
+        None => out,
+    }
+}
+
+/// Round a compact value: one fraction digit below 10, none above.
+fn round_compact(value: &Decimal) -> Decimal {
+    if value.integer_part() < 10 {
+        value.round(1).trim()
+    } else {
+        value.round(0)
+    }
+}
+
+/// Format `value` for `locale` in the given `style`.
+pub(crate) fn format(locale: &str, value: &Decimal, style: NumberStyle) -> String {
+    let symbols = symbols(locale);
+
+    let (value, prefix, suffix) = match style {
+        NumberStyle::Decimal => (value.round(3).trim(), "", String::new()),
+        NumberStyle::Integer => (value.round(0), "", String::new()),
+        NumberStyle::Percent => {
+            (value.shift(2).round(0), symbols.percent.0, symbols.percent.1.to_string())
+        }
+        NumberStyle::Compact => {
+            let (units, separator) = compact_units(&locale.to_ascii_lowercase().replace('_', "-"));
+            let magnitude = value.integer_part().to_string().len() as u32 - 1;
+            match units.iter().rposition(|(power, _)| *power <= magnitude) {
+                Some(mut idx) => {
+                    let mut rounded = round_compact(&value.shift(-(units[idx].0 as i32)));
+                    if let Some(next) = units.get(idx + 1) {
+                        if rounded.integer_part() >= 10u128.pow(next.0 - units[idx].0) {
+                            idx += 1;
+                            rounded = round_compact(&value.shift(-(next.0 as i32)));
+                        }
+                    }
+                    (rounded, "", format!("{}{}", separator, units[idx].1))
+                }
+                None => (round_compact(value), "", String::new()),
+            }
+        }
+    };
+
+    let mut out = String::new();
+    if value.is_negative() {
+        out.push_str(symbols.minus);
+    }
+    out.push_str(prefix);
+    out.push_str(&digits(&value, &symbols));
+    out.push_str(&suffix);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{format, Decimal, NumberStyle::*};
+
+    #[test]
+    fn formats() {
+        let n = Decimal::from(1234567.5);
+
+        assert_eq!(format("en", &n, Decimal), "1,234,567.5");
+        assert_eq!(format("de", &n, Decimal), "1.234.567,5");
+        assert_eq!(format("fr", &n, Decimal), "1\u{202f}234\u{202f}567,5");
+        assert_eq!(format("de-CH", &n, Decimal), "1’234’567.5");
+        assert_eq!(format("hi", &n, Decimal), "12,34,567.5");
+        assert_eq!(format("ar", &n, Decimal), "١٬٢٣٤٬٥٦٧٫٥");
+        assert_eq!(format("en", &n, Integer), "1,234,568");
+        assert_eq!(format("en", &Decimal::from(-0.12345), Decimal), "-0.123");
+        assert_eq!(format("sv", &Decimal::from(-5), Decimal), "−5");
+
+        assert_eq!(format("es", &Decimal::from(1234), Decimal), "1234");
+        assert_eq!(format("es", &Decimal::from(12345), Decimal), "12.345");
+
+        assert_eq!(format("en", &Decimal::from(0.456), Percent), "46%");
+        assert_eq!(format("de", &Decimal::from(0.456), Percent), "46\u{a0}%");
+        assert_eq!(format("tr", &Decimal::from(0.456), Percent), "%46");
+
+        assert_eq!(format("en", &Decimal::from(1234), Compact), "1.2K");
+        assert_eq!(format("en", &Decimal::from(12345), Compact), "12K");
+        assert_eq!(format("en", &Decimal::from(1000), Compact), "1K");
+        assert_eq!(format("en", &Decimal::from(999_999), Compact), "1M");
+        assert_eq!(format("en", &Decimal::from(-1_500_000), Compact), "-1.5M");
+        assert_eq!(format("en", &Decimal::from(999), Compact), "999");
+        assert_eq!(format("de", &Decimal::from(2_500_000), Compact), "2,5\u{a0}Mio.");
+        assert_eq!(format("ja", &Decimal::from(12345), Compact), "1.2万");
+    }
+}
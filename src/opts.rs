@@ -1,6 +1,10 @@
-use std::collections::HashMap;
-
-use super::Key;
+use super::{
+    err,
+    interpolate::MissingVar,
+    rich::Pieces,
+    value::{self, VarMap},
+    Decimal, Key, ListType, Value,
+};
 
 /// Helper for setting `locale` option
 pub struct Locale<'a>(pub &'a str);
@@ -9,7 +13,13 @@ pub struct DefaultKey<T>(pub T);
 /// Helper for setting interpolated variables
 pub struct Var<T, U>(pub T, pub U);
 /// Helper for setting `count` option
-pub struct Count(pub i32);
+pub struct Count<T>(pub T);
+/// Helper for setting `ordinal` option
+pub struct Ordinal<T>(pub T);
+/// Helper for setting `select` option
+pub struct Select<'a, T>(pub T, pub &'a str);
+/// Helper for setting list variables
+pub struct List<T, U>(pub T, pub U, pub ListType);
 
 pub trait OptsPart<'a> {
     fn add_to(self, opts: Opts<'a>) -> Opts<'a>;
@@ -33,19 +43,57 @@ where
 impl<'a, T, U> OptsPart<'a> for Var<T, U>
 where
     T: Into<String>,
-    U: std::fmt::Display,
+    U: Into<Value>,
 {
     fn add_to(self, opts: Opts<'a>) -> Opts<'a> {
         opts.var(self.0, self.1)
     }
 }
 
-impl<'a> OptsPart<'a> for Count {
+impl<'a, T> OptsPart<'a> for Count<T>
+where
+    T: Into<Decimal>,
+{
     fn add_to(self, opts: Opts<'a>) -> Opts<'a> {
         opts.count(self.0)
     }
 }
 
+impl<'a, T> OptsPart<'a> for Ordinal<T>
+where
+    T: Into<Decimal>,
+{
+    fn add_to(self, opts: Opts<'a>) -> Opts<'a> {
+        opts.ordinal(self.0)
+    }
+}
+
+impl<'a, T> OptsPart<'a> for Select<'a, T>
+where
+    T: Into<String>,
+{
+    fn add_to(self, opts: Opts<'a>) -> Opts<'a> {
+        opts.select(self.0, self.1)
+    }
+}
+
+impl<'a, T, U, V> OptsPart<'a> for List<T, U>
+where
+    T: Into<String>,
+    U: IntoIterator<Item = V>,
+    V: Into<String>,
+{
+    fn add_to(self, opts: Opts<'a>) -> Opts<'a> {
+        opts.list(self.0, self.1, self.2)
+    }
+}
+
+impl<'a> OptsPart<'a> for MissingVar {
+    fn add_to(self, opts: Opts<'a>) -> Opts<'a> {
+        opts.missing_var(self)
+    }
+}
+
 impl<'a, T> From<T> for Opts<'a>
 where
     T: OptsPart<'a>,
@@ -100,12 +148,17 @@ where
 }
 
 /// Options for the `translate` call
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Opts<'a> {
     pub(crate) default_key: Option<Key<'a>>,
-    pub(crate) vars: Option<HashMap<String, String>>,
+    pub(crate) vars: VarMap,
     pub(crate) locale: Option<&'a str>,
-    pub(crate) count: Option<i32>,
+    pub(crate) count: Option<Decimal>,
+    pub(crate) ordinal: Option<Decimal>,
+    pub(crate) selects: Vec<&'a str>,
+    pub(crate) missing_var: Option<MissingVar>,
+    pub(crate) rich: Option<&'a Pieces>,
+    pub(crate) html: bool,
 }
 
 impl<'a> Opts<'a> {
@@ -122,25 +175,87 @@ impl<'a> Opts<'a> {
     }
 
     /// Set any variables to be interpolated.
-    pub fn var<I: Into<String>, J: std::fmt::Display>(mut self, key: I, value: J) -> Self {
-        let mut vars = self.vars.take().unwrap_or_else(HashMap::new);
-        vars.insert(key.into(), value.to_string());
-        self.vars = Some(vars);
+    ///
+    /// Values are typed (see `Value`): numbers, dates and lists are formatted for the locale of
+    /// the call, and any variable can select a plural form or branch in ICU messages.
+    pub fn var<I: Into<String>, J: Into<Value>>(mut self, key: I, value: J) -> Self {
+        self.vars.insert(key.into(), value.into());
         self
     }
 
+    /// Set variables from the fields of a struct or the entries of a map (anything that
+    /// implements `serde::Serialize`).
+    ///
+    /// Nested structs and maps are flattened into dotted names, e.g. `{user.name}`. Array items
+    /// are available as `{items.0}`, and arrays of strings or numbers are also list variables.
+    /// Fails if `vars` does not serialize to a map.
+    pub fn vars_from<T: serde::Serialize + ?Sized>(mut self, vars: &T) -> err::Result<Self> {
+        let json = serde_json::to_value(vars)?;
+        if !json.is_object() {
+            return Err(err::custom("Variables must serialize to a map"));
+        }
+        value::flatten("", json, &mut self.vars);
+        Ok(self)
+    }
+
+    /// Set a list variable to be interpolated.
+    ///
+    /// The items are joined with the list pattern of the locale of the call, e.g. "A, B, and C" in
+    /// English or "A, B und C" in German.
+    pub fn list<I, L, S>(self, key: I, items: L, list_type: ListType) -> Self
+    where
+        I: Into<String>,
+        L: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.var(key, Value::list(items, list_type))
+    }
+
+    /// Select a branch of the message by the `value` of a variable (e.g. `gender` = `female`).
+    ///
+    /// Uses the `value` entry of the message, falling back to `other`. The value is also available
+    /// for interpolation as `key`. Can be used more than once (branches are selected in order),
+    /// and is applied before `count` pluralization.
+    pub fn select<I: Into<String>>(mut self, key: I, value: &'a str) -> Self {
+        self.selects.push(value);
+        self.var(key, value)
+    }
+
     /// Set the `count` for this translation.
     ///
-    /// Uses Rails style pluralization options: `zero`, `one`, `other`.
-    pub fn count(mut self, count: i32) -> Self {
+    /// Selects the CLDR plural category for the locale of the call: `zero`, `one`, `two`,
+    /// `few`, `many` or `other`. A Rails style explicit `zero` entry is used for `0` when present,
+    /// and missing categories fall back to `other`.
+    ///
+    /// `count` can be any primitive integer or float, or a `Decimal` (to keep visible fraction
+    /// digits, e.g. `1.50`).
+    pub fn count<N: Into<Decimal>>(mut self, count: N) -> Self {
+        let count = count.into();
         self.count = Some(count);
         self.var("count", count)
     }
+
+    /// Set the ordinal `count` for this translation (e.g. for "1st", "2nd", "3rd").
+    ///
+    /// Selects the CLDR ordinal category for the locale of the call: `zero`, `one`, `two`,
+    /// `few`, `many` or `other`, falling back to `other`. The value is available as `count`.
+    pub fn ordinal<N: Into<Decimal>>(mut self, count: N) -> Self {
+        let count = count.into();
+        self.ordinal = Some(count);
+        self.var("count", count)
+    }
+
+    /// Set what to do when the message references a variable that was not passed, overriding
+    /// `Config::with_missing_var`.
+    pub fn missing_var(mut self, missing_var: MissingVar) -> Self {
+        self.missing_var = Some(missing_var);
+        self
+    }
 }
 
 impl<'a> From<Option<Opts<'a>>> for Opts<'a> {
     fn from(t: Option<Opts<'a>>) -> Self {
-        t.unwrap_or_else(Opts::default)
+        t.unwrap_or_default()
     }
 }
 
@@ -0,0 +1,808 @@
+//! CLDR plural rules.
+//!
+//! Rules are transcribed from the CLDR `plurals.xml` supplemental data and are selected by the
+//! language subtag of the locale (e.g. `ru` for `ru-RU`).
+
+use super::Decimal;
+
+/// CLDR plural category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The catalog key for this category.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// CLDR plural operands for a `count`, as passed to custom plural rules
+///
+/// For `-1.230`: `n` = `1.23`, `i` = `1`, `v` = `3`, `w` = `2`, `f` = `230`, `t` = `23`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PluralOperands {
+    /// Absolute value of the source number
+    pub n: f64,
+    /// Integer digits of `n`
+    pub i: u128,
+    /// Number of visible fraction digits, with trailing zeros
+    pub v: u32,
+    /// Number of visible fraction digits, without trailing zeros
+    pub w: u32,
+    /// Visible fraction digits, with trailing zeros
+    pub f: u128,
+    /// Visible fraction digits, without trailing zeros
+    pub t: u128,
+}
+
+impl From<&Decimal> for PluralOperands {
+    fn from(t: &Decimal) -> Self {
+        let (v, f) = (t.scale(), t.fraction_part());
+        let (mut w, mut t_) = (v, f);
+        while w > 0 && t_ % 10 == 0 {
+            w -= 1;
+            t_ /= 10;
+        }
+        Self { n: t.to_f64().abs(), i: t.integer_part(), v, w, f, t: t_ }
+    }
+}
+
+/// A user-supplied rule mapping a count to a plural category name (e.g. `one`)
+#[derive(Clone)]
+pub(crate) struct CustomRule(
+    pub(crate) std::sync::Arc<dyn Fn(&PluralOperands) -> &'static str + Send + Sync>,
+);
+
+impl std::fmt::Debug for CustomRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("CustomRule")
+    }
+}
+
+/// Get the language subtag of `locale`, with regional variants that have their own rules kept.
+pub(crate) fn language(locale: &str) -> String {
+    let locale = locale.to_ascii_lowercase().replace('_', "-");
+    if locale == "pt-pt" || locale.starts_with("pt-pt-") {
+        return "pt-pt".into();
+    }
+    match locale.find('-') {
+        Some(idx) => locale[..idx].into(),
+        None => locale,
+    }
+}
+
+/// `true` if `x` is an integer within `lo..=hi`.
+fn within(x: f64, lo: u64, hi: u64) -> bool {
+    x.fract() == 0.0 && x >= lo as f64 && x <= hi as f64
+}
+
+/// `true` if `x` is one of the integers in `set`.
+fn one_of(x: f64, set: &[u64]) -> bool {
+    x.fract() == 0.0 && set.iter().any(|y| x == *y as f64)
+}
+
+/// Select the CLDR cardinal plural category for `locale`.
+// Conditions are kept in the same shape as the CLDR rule text.
+#[allow(clippy::nonminimal_bool)]
+pub(crate) fn cardinal(locale: &str, ops: &PluralOperands) -> PluralCategory {
+    use PluralCategory::*;
+
+    let PluralOperands { n, i, v, f, t, .. } = *ops;
+
+    let millions = |one| {
+        if one {
+            One
+        } else if i != 0 && i % 1_000_000 == 0 && v == 0 {
+            Many
+        } else {
+            Other
+        }
+    };
+
+    match language(locale).as_str() {
+        "af" | "an" | "asa" | "az" | "bal" | "bem" | "bez" | "bg" | "brx" | "ce" | "cgg"
+        | "chr" | "ckb" | "dv" | "ee" | "el" | "eo" | "eu" | "fo" | "fur" | "gsw" | "ha"
+        | "haw" | "hu" | "jgo" | "jmc" | "ka" | "kaj" | "kcg" | "kk" | "kkj" | "kl" | "ks"
+        | "ksb" | "ku" | "ky" | "lb" | "lg" | "mas" | "mgo" | "ml" | "mn" | "mr" | "nah" | "nb"
+        | "nd" | "ne" | "nn" | "nnh" | "no" | "nr" | "ny" | "nyn" | "om" | "or" | "os" | "pap"
+        | "ps" | "rm" | "rof" | "rwk" | "saq" | "sd" | "sdh" | "seh" | "sn" | "so" | "sq"
+        | "ss" | "ssy" | "st" | "syr" | "ta" | "te" | "teo" | "tig" | "tk" | "tn" | "tr" | "ts"
+        | "ug" | "uz" | "ve" | "vo" | "vun" | "wae" | "xh" | "xog" => {
+            if n == 1.0 {
+                One
+            } else {
+                Other
+            }
+        }
+        "ast" | "de" | "en" | "et" | "fi" | "fy" | "gl" | "ia" | "io" | "ji" | "lij" | "nl"
+        | "sc" | "sv" | "sw" | "ur" | "yi" => {
+            if i == 1 && v == 0 {
+                One
+            } else {
+                Other
+            }
+        }
+        "ca" | "it" | "pt-pt" => millions(i == 1 && v == 0),
+        "es" => millions(n == 1.0),
+        "fr" | "pt" => {
+            if i <= 1 {
+                One
+            } else {
+                millions(false)
+            }
+        }
+        "ff" | "hy" | "kab" => {
+            if i <= 1 {
+                One
+            } else {
+                Other
+            }
+        }
+        "am" | "as" | "bn" | "doi" | "fa" | "gu" | "hi" | "kn" | "pcm" | "zu" => {
+            if i == 0 || n == 1.0 {
+                One
+            } else {
+                Other
+            }
+        }
+        "ak" | "bho" | "csw" | "guw" | "ln" | "mg" | "nso" | "pa" | "ti" | "wa" => {
+            if within(n, 0, 1) {
+                One
+            } else {
+                Other
+            }
+        }
+        "tzm" => {
+            if within(n, 0, 1) || within(n, 11, 99) {
+                One
+            } else {
+                Other
+            }
+        }
+        "si" => {
+            if one_of(n, &[0, 1]) || (i == 0 && f == 1) {
+                One
+            } else {
+                Other
+            }
+        }
+        "da" => {
+            if n == 1.0 || (t != 0 && i <= 1) {
+                One
+            } else {
+                Other
+            }
+        }
+        "is" => {
+            if (t == 0 && i % 10 == 1 && i % 100 != 11) || (t % 10 == 1 && t % 100 != 11) {
+                One
+            } else {
+                Other
+            }
+        }
+        "mk" => {
+            if (v == 0 && i % 10 == 1 && i % 100 != 11) || (f % 10 == 1 && f % 100 != 11) {
+                One
+            } else {
+                Other
+            }
+        }
+        "ceb" | "fil" | "tl" => {
+            if (v == 0 && (1..=3).contains(&i))
+                || (v == 0 && ![4, 6, 9].contains(&(i % 10)))
+                || (v != 0 && ![4, 6, 9].contains(&(f % 10)))
+            {
+                One
+            } else {
+                Other
+            }
+        }
+        "lv" | "prg" => {
+            if n % 10.0 == 0.0
+                || within(n % 100.0, 11, 19)
+                || (v == 2 && (11..=19).contains(&(f % 100)))
+            {
+                Zero
+            } else if (n % 10.0 == 1.0 && n % 100.0 != 11.0)
+                || (v == 2 && f % 10 == 1 && f % 100 != 11)
+                || (v != 2 && f % 10 == 1)
+            {
+                One
+            } else {
+                Other
+            }
+        }
+        "lag" => {
+            if n == 0.0 {
+                Zero
+            } else if i <= 1 {
+                One
+            } else {
+                Other
+            }
+        }
+        "ksh" => {
+            if n == 0.0 {
+                Zero
+            } else if n == 1.0 {
+                One
+            } else {
+                Other
+            }
+        }
+        "he" | "iw" => {
+            if (i == 1 && v == 0) || (i == 0 && v != 0) {
+                One
+            } else if i == 2 && v == 0 {
+                Two
+            } else {
+                Other
+            }
+        }
+        "iu" | "naq" | "sat" | "se" | "sma" | "smi" | "smj" | "smn" | "sms" => {
+            if n == 1.0 {
+                One
+            } else if n == 2.0 {
+                Two
+            } else {
+                Other
+            }
+        }
+        "shi" => {
+            if i == 0 || n == 1.0 {
+                One
+            } else if within(n, 2, 10) {
+                Few
+            } else {
+                Other
+            }
+        }
+        "mo" | "ro" => {
+            if i == 1 && v == 0 {
+                One
+            } else if v != 0 || n == 0.0 || (n != 1.0 && within(n % 100.0, 1, 19)) {
+                Few
+            } else {
+                Other
+            }
+        }
+        "bs" | "hr" | "sh" | "sr" => {
+            if (v == 0 && i % 10 == 1 && i % 100 != 11) || (f % 10 == 1 && f % 100 != 11) {
+                One
+            } else if (v == 0 && (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)))
+                || ((2..=4).contains(&(f % 10)) && !(12..=14).contains(&(f % 100)))
+            {
+                Few
+            } else {
+                Other
+            }
+        }
+        "gd" => {
+            if one_of(n, &[1, 11]) {
+                One
+            } else if one_of(n, &[2, 12]) {
+                Two
+            } else if within(n, 3, 10) || within(n, 13, 19) {
+                Few
+            } else {
+                Other
+            }
+        }
+        "sl" => {
+            if v == 0 && i % 100 == 1 {
+                One
+            } else if v == 0 && i % 100 == 2 {
+                Two
+            } else if (v == 0 && (3..=4).contains(&(i % 100))) || v != 0 {
+                Few
+            } else {
+                Other
+            }
+        }
+        "dsb" | "hsb" => {
+            if (v == 0 && i % 100 == 1) || f % 100 == 1 {
+                One
+            } else if (v == 0 && i % 100 == 2) || f % 100 == 2 {
+                Two
+            } else if (v == 0 && (3..=4).contains(&(i % 100))) || (3..=4).contains(&(f % 100)) {
+                Few
+            } else {
+                Other
+            }
+        }
+        "cs" | "sk" => {
+            if i == 1 && v == 0 {
+                One
+            } else if (2..=4).contains(&i) && v == 0 {
+                Few
+            } else if v != 0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "pl" => {
+            if i == 1 && v == 0 {
+                One
+            } else if v == 0 && (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {
+                Few
+            } else if v == 0
+                && ((i != 1 && i % 10 <= 1)
+                    || (5..=9).contains(&(i % 10))
+                    || (12..=14).contains(&(i % 100)))
+            {
+                Many
+            } else {
+                Other
+            }
+        }
+        "be" => {
+            if n % 10.0 == 1.0 && n % 100.0 != 11.0 {
+                One
+            } else if within(n % 10.0, 2, 4) && !within(n % 100.0, 12, 14) {
+                Few
+            } else if n % 10.0 == 0.0 || within(n % 10.0, 5, 9) || within(n % 100.0, 11, 14) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "lt" => {
+            if n % 10.0 == 1.0 && !within(n % 100.0, 11, 19) {
+                One
+            } else if within(n % 10.0, 2, 9) && !within(n % 100.0, 11, 19) {
+                Few
+            } else if f != 0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "ru" | "uk" => {
+            if v == 0 && i % 10 == 1 && i % 100 != 11 {
+                One
+            } else if v == 0 && (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {
+                Few
+            } else if v == 0
+                && (i % 10 == 0 || (5..=9).contains(&(i % 10)) || (11..=14).contains(&(i % 100)))
+            {
+                Many
+            } else {
+                Other
+            }
+        }
+        "br" => {
+            if n % 10.0 == 1.0 && !one_of(n % 100.0, &[11, 71, 91]) {
+                One
+            } else if n % 10.0 == 2.0 && !one_of(n % 100.0, &[12, 72, 92]) {
+                Two
+            } else if (within(n % 10.0, 3, 4) || n % 10.0 == 9.0)
+                && !(within(n % 100.0, 10, 19)
+                    || within(n % 100.0, 70, 79)
+                    || within(n % 100.0, 90, 99))
+            {
+                Few
+            } else if n != 0.0 && n % 1_000_000.0 == 0.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "mt" => {
+            if n == 1.0 {
+                One
+            } else if n == 2.0 {
+                Two
+            } else if n == 0.0 || within(n % 100.0, 3, 10) {
+                Few
+            } else if within(n % 100.0, 11, 19) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "ga" => {
+            if n == 1.0 {
+                One
+            } else if n == 2.0 {
+                Two
+            } else if within(n, 3, 6) {
+                Few
+            } else if within(n, 7, 10) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "gv" => {
+            if v == 0 && i % 10 == 1 {
+                One
+            } else if v == 0 && i % 10 == 2 {
+                Two
+            } else if v == 0 && [0, 20, 40, 60, 80].contains(&(i % 100)) {
+                Few
+            } else if v != 0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "kw" => {
+            if n == 0.0 {
+                Zero
+            } else if n == 1.0 {
+                One
+            } else if one_of(n % 100.0, &[2, 22, 42, 62, 82])
+                || (n % 1000.0 == 0.0
+                    && (within(n % 100_000.0, 1000, 20000)
+                        || one_of(n % 100_000.0, &[40000, 60000, 80000])))
+                || (n != 0.0 && n % 1_000_000.0 == 100_000.0)
+            {
+                Two
+            } else if one_of(n % 100.0, &[3, 23, 43, 63, 83]) {
+                Few
+            } else if n != 1.0 && one_of(n % 100.0, &[1, 21, 41, 61, 81]) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "ar" | "ars" => {
+            if n == 0.0 {
+                Zero
+            } else if n == 1.0 {
+                One
+            } else if n == 2.0 {
+                Two
+            } else if within(n % 100.0, 3, 10) {
+                Few
+            } else if within(n % 100.0, 11, 99) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "cy" => {
+            if n == 0.0 {
+                Zero
+            } else if n == 1.0 {
+                One
+            } else if n == 2.0 {
+                Two
+            } else if n == 3.0 {
+                Few
+            } else if n == 6.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        _ => Other,
+    }
+}
+
+/// Select the CLDR ordinal plural category for `locale`.
+pub(crate) fn ordinal(locale: &str, ops: &PluralOperands) -> PluralCategory {
+    use PluralCategory::*;
+
+    let PluralOperands { n, i, .. } = *ops;
+
+    match language(locale).as_str() {
+        "en" => {
+            if n % 10.0 == 1.0 && n % 100.0 != 11.0 {
+                One
+            } else if n % 10.0 == 2.0 && n % 100.0 != 12.0 {
+                Two
+            } else if n % 10.0 == 3.0 && n % 100.0 != 13.0 {
+                Few
+            } else {
+                Other
+            }
+        }
+        "bal" | "fil" | "fr" | "ga" | "hy" | "lo" | "mo" | "ms" | "ro" | "tl" | "vi" => {
+            if n == 1.0 {
+                One
+            } else {
+                Other
+            }
+        }
+        "sv" => {
+            if one_of(n % 10.0, &[1, 2]) && !one_of(n % 100.0, &[11, 12]) {
+                One
+            } else {
+                Other
+            }
+        }
+        "hu" => {
+            if one_of(n, &[1, 5]) {
+                One
+            } else {
+                Other
+            }
+        }
+        "ne" => {
+            if within(n, 1, 4) {
+                One
+            } else {
+                Other
+            }
+        }
+        "it" | "sc" => {
+            if one_of(n, &[11, 8, 80, 800]) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "lij" => {
+            if one_of(n, &[11, 8]) || within(n, 80, 89) || within(n, 800, 899) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "kk" => {
+            if n % 10.0 == 6.0 || n % 10.0 == 9.0 || (n % 10.0 == 0.0 && n != 0.0) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "ka" => {
+            if i == 1 {
+                One
+            } else if i == 0 || (2..=20).contains(&(i % 100)) || [40, 60, 80].contains(&(i % 100)) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "sq" => {
+            if n == 1.0 {
+                One
+            } else if n % 10.0 == 4.0 && n % 100.0 != 14.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "uk" => {
+            if n % 10.0 == 3.0 && n % 100.0 != 13.0 {
+                Few
+            } else {
+                Other
+            }
+        }
+        "tk" => {
+            if one_of(n % 10.0, &[6, 9]) || n == 10.0 {
+                Few
+            } else {
+                Other
+            }
+        }
+        "be" => {
+            if one_of(n % 10.0, &[2, 3]) && !one_of(n % 100.0, &[12, 13]) {
+                Few
+            } else {
+                Other
+            }
+        }
+        "ca" => {
+            if one_of(n, &[1, 3]) {
+                One
+            } else if n == 2.0 {
+                Two
+            } else if n == 4.0 {
+                Few
+            } else {
+                Other
+            }
+        }
+        "mk" => {
+            if i % 10 == 1 && i % 100 != 11 {
+                One
+            } else if i % 10 == 2 && i % 100 != 12 {
+                Two
+            } else if [7, 8].contains(&(i % 10)) && ![17, 18].contains(&(i % 100)) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "az" => {
+            if [1, 2, 5, 7, 8].contains(&(i % 10)) || [20, 50, 70, 80].contains(&(i % 100)) {
+                One
+            } else if [3, 4].contains(&(i % 10)) || (i % 100 == 0 && i % 1000 != 0) {
+                Few
+            } else if i == 0 || i % 10 == 6 || [40, 60, 90].contains(&(i % 100)) {
+                Many
+            } else {
+                Other
+            }
+        }
+        "gu" | "hi" => {
+            if n == 1.0 {
+                One
+            } else if one_of(n, &[2, 3]) {
+                Two
+            } else if n == 4.0 {
+                Few
+            } else if n == 6.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "as" | "bn" => {
+            if one_of(n, &[1, 5, 7, 8, 9, 10]) {
+                One
+            } else if one_of(n, &[2, 3]) {
+                Two
+            } else if n == 4.0 {
+                Few
+            } else if n == 6.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "or" => {
+            if one_of(n, &[1, 5]) || within(n, 7, 9) {
+                One
+            } else if one_of(n, &[2, 3]) {
+                Two
+            } else if n == 4.0 {
+                Few
+            } else if n == 6.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "mr" => {
+            if n == 1.0 {
+                One
+            } else if one_of(n, &[2, 3]) {
+                Two
+            } else if n == 4.0 {
+                Few
+            } else {
+                Other
+            }
+        }
+        "gd" => {
+            if one_of(n, &[1, 11]) {
+                One
+            } else if one_of(n, &[2, 12]) {
+                Two
+            } else if one_of(n, &[3, 13]) {
+                Few
+            } else {
+                Other
+            }
+        }
+        "kw" => {
+            let rem = n % 100.0;
+            if within(n, 1, 4) || [1, 21, 41, 61, 81].iter().any(|lo| within(rem, *lo, lo + 3)) {
+                One
+            } else if n == 5.0 || rem == 5.0 {
+                Many
+            } else {
+                Other
+            }
+        }
+        "cy" => {
+            if one_of(n, &[0, 7, 8, 9]) {
+                Zero
+            } else if n == 1.0 {
+                One
+            } else if n == 2.0 {
+                Two
+            } else if one_of(n, &[3, 4]) {
+                Few
+            } else if one_of(n, &[5, 6]) {
+                Many
+            } else {
+                Other
+            }
+        }
+        _ => Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{cardinal, ordinal, PluralCategory::*, PluralOperands};
+    use crate::Decimal;
+
+    fn card<T: Into<Decimal>>(locale: &str, n: T) -> super::PluralCategory {
+        cardinal(locale, &PluralOperands::from(&n.into()))
+    }
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn cardinals() {
+        assert_eq!(card("en", 1), One);
+        assert_eq!(card("en-US", 2), Other);
+        assert_eq!(card("fr", 0), One);
+        assert_eq!(card("fr", 1_000_000), Many);
+        assert_eq!(card("pt_PT", 0), Other);
+        assert_eq!(card("pt-BR", 0), One);
+
+        assert_eq!(card("ru", 1), One);
+        assert_eq!(card("ru", 3), Few);
+        assert_eq!(card("ru", 11), Many);
+        assert_eq!(card("ru", 21), One);
+        assert_eq!(card("ru", 25), Many);
+
+        assert_eq!(card("pl", 1), One);
+        assert_eq!(card("pl", 22), Few);
+        assert_eq!(card("pl", 12), Many);
+        assert_eq!(card("pl", 21), Many);
+
+        assert_eq!(card("ar", 0), Zero);
+        assert_eq!(card("ar", 2), Two);
+        assert_eq!(card("ar", 103), Few);
+        assert_eq!(card("ar", 111), Many);
+        assert_eq!(card("ar", 100), Other);
+
+        assert_eq!(card("cy", 3), Few);
+        assert_eq!(card("cy", 6), Many);
+        assert_eq!(card("cy", 4), Other);
+
+        assert_eq!(card("ja", 1), Other);
+        assert_eq!(card("xx", 1), Other);
+    }
+
+    #[test]
+    fn decimal_operands() {
+        let ops = PluralOperands::from(&dec("-1.230"));
+        assert_eq!((ops.n, ops.i, ops.v, ops.w, ops.f, ops.t), (1.23, 1, 3, 2, 230, 23));
+
+        assert_eq!(card("en", 1.5), Other);
+        assert_eq!(card("fr", 1.5), One);
+        assert_eq!(card("en", dec("1.0")), Other);
+        assert_eq!(card("ru", 1.5), Other);
+        assert_eq!(card("cs", 1.5), Many);
+        assert_eq!(card("lv", dec("0.11")), Zero);
+        assert_eq!(card("is", 21.1), One);
+        assert_eq!(card("ru", u64::MAX), Many);
+        assert_eq!(card("fr", 3_000_000_u64), Many);
+    }
+
+    #[test]
+    fn ordinals() {
+        let ord = |locale, n: i32| ordinal(locale, &PluralOperands::from(&n.into()));
+
+        assert_eq!(ord("en", 1), One);
+        assert_eq!(ord("en", 22), Two);
+        assert_eq!(ord("en", 103), Few);
+        assert_eq!(ord("en", 11), Other);
+        assert_eq!(ord("en", 112), Other);
+
+        assert_eq!(ord("fr", 1), One);
+        assert_eq!(ord("fr", 2), Other);
+        assert_eq!(ord("sv", 32), One);
+        assert_eq!(ord("it", 8), Many);
+        assert_eq!(ord("cy", 7), Zero);
+        assert_eq!(ord("de", 1), Other);
+    }
+}
@@ -0,0 +1,369 @@
+//! Pseudo-localization: synthetic locales generated from the default locale, to find hard-coded
+//! strings, truncation and layout bugs before the messages are translated.
+
+use super::{interpolate::Interpolation, link};
+
+/// Words appended to expand messages
+const FILLER: &str = "one two three four five six seven eight nine ten eleven twelve thirteen";
+
+/// Right-to-left override and pop directional formatting, to mirror words
+const RLO: char = '\u{202e}';
+const PDF: char = '\u{202c}';
+
+/// A pseudo-locale, generated from the messages of the default locale (also a helper for setting
+/// `pseudo_locale` configuration)
+///
+/// Letters in the message text are replaced, and the messages are expanded and wrapped in
+/// brackets, but placeholders, ICU syntax, `$t(key)` links, HTML tags and `strftime` directives
+/// are kept, so messages still interpolate and format as usual.
+///
+/// ```rust
+/// use loon::prelude::*;
+/// let dict = Config::default()
+///     .with_path_pattern("examples/locales/*.yml")
+///     .with_pseudo_locale(PseudoLocale::accented().with_expansion(0.5))
+///     .finish()
+///     .unwrap();
+/// assert_eq!(
+///     dict.translate("custom.greeting", (Locale("en-XA"), Var("name", "Jacob"))).unwrap(),
+///     "[Ĥéļļö, Jacob!!! one t]"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PseudoLocale {
+    locale: String,
+    expansion: f64,
+    brackets: bool,
+    accents: bool,
+    mirror: bool,
+}
+
+impl PseudoLocale {
+    /// `en-XA`: accented letters, 30% longer messages and bracket markers.
+    pub fn accented() -> Self {
+        Self {
+            locale: "en-XA".into(),
+            expansion: 0.3,
+            brackets: true,
+            accents: true,
+            mirror: false,
+        }
+    }
+
+    /// `ar-XB`: words mirrored right-to-left, to check bidirectional layouts.
+    pub fn bidi() -> Self {
+        Self {
+            locale: "ar-XB".into(),
+            expansion: 0.0,
+            brackets: false,
+            accents: false,
+            mirror: true,
+        }
+    }
+
+    /// Set the name of the locale.
+    pub fn with_locale<I: Into<String>>(mut self, locale: I) -> Self {
+        self.locale = locale.into();
+        self
+    }
+
+    /// Make messages longer by `expansion` times the length of their text (e.g. `0.3` for 30%).
+    pub fn with_expansion(mut self, expansion: f64) -> Self {
+        self.expansion = expansion.max(0.0);
+        self
+    }
+
+    /// Set whether to wrap messages in `[` and `]`, to show truncation and concatenation.
+    pub fn with_brackets(mut self, brackets: bool) -> Self {
+        self.brackets = brackets;
+        self
+    }
+
+    /// Set whether to replace ASCII letters with accented ones (`Hello` -> `Ĥéļļö`).
+    pub fn with_accents(mut self, accents: bool) -> Self {
+        self.accents = accents;
+        self
+    }
+
+    /// Set whether to mirror words right-to-left (with Unicode bidi overrides).
+    pub fn with_mirror(mut self, mirror: bool) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    pub(crate) fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Pseudo-localize a message.
+    pub(crate) fn message(&self, message: &str, syntax: Option<Interpolation>) -> String {
+        let pieces = pieces(message, syntax);
+        let text_len = pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Text(text) => Some(text.chars().count()),
+                Piece::Verbatim(_) => None,
+            })
+            .sum::<usize>();
+        let has_letters = pieces.iter().any(|piece| match piece {
+            Piece::Text(text) => text.chars().any(char::is_alphabetic),
+            Piece::Verbatim(_) => false,
+        });
+
+        if !has_letters {
+            return message.to_string();
+        }
+
+        let mut out = String::with_capacity(message.len() * 2);
+        if self.brackets {
+            out.push('[');
+        }
+        for piece in pieces {
+            match piece {
+                Piece::Verbatim(text) => out.push_str(text),
+                Piece::Text(text) => self.text(&mut out, text),
+            }
+        }
+        let extra = (text_len as f64 * self.expansion).round() as usize;
+        if extra > 0 {
+            out.push(' ');
+            out.push_str(FILLER.chars().cycle().take(extra).collect::<String>().trim_end());
+        }
+        if self.brackets {
+            out.push(']');
+        }
+        out
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        let mut in_word = false;
+        for c in text.chars() {
+            if self.mirror && in_word == c.is_whitespace() {
+                out.push(if in_word { PDF } else { RLO });
+                in_word = !in_word;
+            }
+            out.push(if self.accents { accent(c) } else { c });
+        }
+        if in_word {
+            out.push(PDF);
+        }
+    }
+}
+
+fn accent(c: char) -> char {
+    match c {
+        'a' => 'å',
+        'b' => 'ƀ',
+        'c' => 'ç',
+        'd' => 'ð',
+        'e' => 'é',
+        'f' => 'ƒ',
+        'g' => 'ĝ',
+        'h' => 'ĥ',
+        'i' => 'î',
+        'j' => 'ĵ',
+        'k' => 'ķ',
+        'l' => 'ļ',
+        'm' => 'ɱ',
+        'n' => 'ñ',
+        'o' => 'ö',
+        'p' => 'þ',
+        'q' => 'ǫ',
+        'r' => 'ŕ',
+        's' => 'š',
+        't' => 'ţ',
+        'u' => 'û',
+        'v' => 'ṽ',
+        'w' => 'ŵ',
+        'x' => 'ẋ',
+        'y' => 'ý',
+        'z' => 'ž',
+        'A' => 'Å',
+        'B' => 'Ɓ',
+        'C' => 'Ç',
+        'D' => 'Ð',
+        'E' => 'É',
+        'F' => 'Ƒ',
+        'G' => 'Ĝ',
+        'H' => 'Ĥ',
+        'I' => 'Î',
+        'J' => 'Ĵ',
+        'K' => 'Ķ',
+        'L' => 'Ļ',
+        'M' => 'Ṁ',
+        'N' => 'Ñ',
+        'O' => 'Ö',
+        'P' => 'Þ',
+        'Q' => 'Ǫ',
+        'R' => 'Ŕ',
+        'S' => 'Š',
+        'T' => 'Ţ',
+        'U' => 'Û',
+        'V' => 'Ṽ',
+        'W' => 'Ŵ',
+        'X' => 'Ẋ',
+        'Y' => 'Ý',
+        'Z' => 'Ž',
+        c => c,
+    }
+}
+
+/// A piece of a message
+#[derive(Debug, PartialEq)]
+enum Piece<'s> {
+    /// Text to pseudo-localize
+    Text(&'s str),
+    /// Placeholders, ICU syntax, links, tags, entities and `strftime` directives
+    Verbatim(&'s str),
+}
+
+/// Length of the HTML tag or entity at the start of `rest`, if any.
+fn markup(rest: &str) -> Option<usize> {
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some('<'), Some(c)) if c.is_ascii_alphabetic() || c == '/' => {
+            rest.find('>').map(|e| e + 1)
+        }
+        (Some('&'), Some(_)) => {
+            let end = rest.find(';')?;
+            let name = &rest[1..end];
+            if !name.is_empty()
+                && name.len() <= 8
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '#')
+            {
+                Some(end + 1)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Length of the `strftime` directive (`%B`, `%-d`) at the start of `rest`, if any.
+fn directive(rest: &str) -> Option<usize> {
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('%'), Some('-'), Some(c)) if c.is_ascii_alphabetic() => Some(3),
+        (Some('%'), Some(c), _) if c.is_ascii_alphabetic() => Some(2),
+        _ => None,
+    }
+}
+
+/// Split `message` into text and verbatim pieces.
+fn pieces(message: &str, syntax: Option<Interpolation>) -> Vec<Piece<'_>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+    // ICU nesting: `true` in an argument, `false` in a branch message of an argument
+    let mut stack: Vec<bool> = Vec::new();
+
+    while idx < message.len() {
+        let rest = &message[idx..];
+        let c = rest.chars().next().unwrap_or_default();
+        let closing = |pat: &str, skip: usize| {
+            rest[skip..].find(pat).map(|end| skip + end + pat.len()).unwrap_or(rest.len())
+        };
+
+        let verbatim = if stack.last() == Some(&true) {
+            match c {
+                '{' => stack.push(false),
+                '}' => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+            Some(c.len_utf8())
+        } else if let Some((_, len)) = link::reference(rest) {
+            Some(len)
+        } else if let Some(len) = markup(rest) {
+            Some(len)
+        } else {
+            match syntax {
+                None => match c {
+                    '{' => {
+                        stack.push(true);
+                        Some(1)
+                    }
+                    '}' if !stack.is_empty() => {
+                        stack.pop();
+                        Some(1)
+                    }
+                    '#' if !stack.is_empty() => Some(1),
+                    '\'' if rest.starts_with("''") => Some(2),
+                    '\'' if rest[1..].starts_with(['{', '}', '#', '|']) => Some(closing("'", 1)),
+                    _ => None,
+                },
+                Some(Interpolation::Brace) if rest.starts_with("{{") || rest.starts_with("}}") => {
+                    Some(2)
+                }
+                Some(Interpolation::Brace) if c == '{' => Some(closing("}", 1)),
+                Some(Interpolation::Percent) if rest.starts_with("%%") => Some(2),
+                Some(Interpolation::Percent) if rest.starts_with("%{") => Some(closing("}", 2)),
+                Some(Interpolation::DoubleBrace) if rest.starts_with("\\{{") => Some(3),
+                Some(Interpolation::DoubleBrace) if rest.starts_with("{{") => {
+                    Some(closing("}}", 2))
+                }
+                _ => None,
+            }
+            .or_else(|| directive(rest))
+        };
+
+        match verbatim {
+            Some(len) => {
+                if idx > start {
+                    out.push(Piece::Text(&message[start..idx]));
+                }
+                out.push(Piece::Verbatim(&message[idx..idx + len]));
+                idx += len;
+                start = idx;
+            }
+            None => idx += c.len_utf8(),
+        }
+    }
+
+    if idx > start {
+        out.push(Piece::Text(&message[start..idx]));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Interpolation::*, PseudoLocale};
+
+    #[test]
+    fn pseudo_localizes() {
+        let xa = PseudoLocale::accented().with_expansion(0.0);
+
+        assert_eq!(xa.message("Hello, {name}!", Some(Brace)), "[Ĥéļļö, {name}!]");
+        assert_eq!(xa.message("Hi %{name}, 100%%", Some(Percent)), "[Ĥî %{name}, 100%%]");
+        assert_eq!(
+            xa.message("Hi {{ name }} \\{{x}}", Some(DoubleBrace)),
+            "[Ĥî {{ name }} \\{{ẋ}}]"
+        );
+        assert_eq!(
+            xa.message("<a href=\"/x\">Terms</a> &amp; $t(app.name)", Some(Brace)),
+            "[<a href=\"/x\">Ţéŕɱš</a> &amp; $t(app.name)]"
+        );
+        assert_eq!(
+            xa.message("{count, plural, one {# item} other {# items for {name}}}", None),
+            "[{count, plural, one {# îţéɱ} other {# îţéɱš ƒöŕ {name}}}]"
+        );
+        assert_eq!(xa.message("It''s '{quoted}'", None), "[Îţ''š '{quoted}']");
+        assert_eq!(xa.message("%B %-d, %Y", Some(Brace)), "%B %-d, %Y");
+        assert_eq!(xa.message("$t(app.name)", Some(Brace)), "$t(app.name)");
+        assert_eq!(xa.message("{name}", Some(Brace)), "{name}");
+
+        let expanded = PseudoLocale::accented();
+        assert_eq!(expanded.message("Hello world", Some(Brace)), "[Ĥéļļö ŵöŕļð one]");
+
+        let xb = PseudoLocale::bidi();
+        assert_eq!(
+            xb.message("Hi {name} there", Some(Brace)),
+            "\u{202e}Hi\u{202c} {name} \u{202e}there\u{202c}"
+        );
+    }
+}
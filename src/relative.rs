@@ -0,0 +1,268 @@
+//! Relative time formatting ("3 minutes ago", "in 2 days", "yesterday").
+//!
+//! Messages come from the `relative_time` entries of the locale files, using the CLDR field names
+//! (`day`, `day-short`, `day-narrow`, ...):
+//!
+//! ```yaml
+//! relative_time:
+//!   day:
+//!     past: { one: "{count} day ago", other: "{count} days ago" }
+//!     future: { one: "in {count} day", other: "in {count} days" }
+//!     "-1": yesterday
+//!     "0": today
+//!     "1": tomorrow
+//! ```
+//!
+//! English messages are bundled, so English works without any catalog data.
+
+use super::DateTime;
+
+/// Length of the unit names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelativeStyle {
+    /// "in 3 minutes" (the default)
+    #[default]
+    Long,
+    /// "in 3 min."
+    Short,
+    /// "in 3m"
+    Narrow,
+}
+
+/// Whether to use phrases like "yesterday" and "tomorrow"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelativeNumeric {
+    /// Always use a number: "1 day ago", "in 0 seconds" (the default)
+    #[default]
+    Always,
+    /// Use "yesterday", "today", "tomorrow", "now", "last week", etc. when available
+    Auto,
+}
+
+/// A time relative to now (or to another time), to be formatted as "3 minutes ago"
+///
+/// ```rust
+/// use loon::{DateTime, RelativeTime};
+/// let event = RelativeTime::between(DateTime::date(2020, 3, 4), DateTime::date(2020, 3, 2));
+/// assert_eq!(event.seconds(), -2 * 86_400);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RelativeTime {
+    seconds: i64,
+    style: RelativeStyle,
+    numeric: RelativeNumeric,
+}
+
+/// CLDR relative time units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Unit::Second => "second",
+            Unit::Minute => "minute",
+            Unit::Hour => "hour",
+            Unit::Day => "day",
+            Unit::Week => "week",
+            Unit::Month => "month",
+            Unit::Year => "year",
+        }
+    }
+}
+
+fn timestamp(t: &DateTime) -> i64 {
+    t.days() * 86_400 + i64::from(t.hour() * 3600 + t.minute() * 60 + t.second())
+}
+
+/// `n / d`, rounded half away from zero.
+fn div_round(n: i64, d: i64) -> i64 {
+    let q = (n.abs() + d / 2) / d;
+    if n < 0 {
+        -q
+    } else {
+        q
+    }
+}
+
+impl RelativeTime {
+    /// A time `seconds` from now (negative for the past).
+    pub fn seconds_from_now(seconds: i64) -> Self {
+        Self { seconds, ..Self::default() }
+    }
+
+    /// A time `duration` ago.
+    pub fn ago(duration: std::time::Duration) -> Self {
+        Self::seconds_from_now(-(duration.as_secs() as i64))
+    }
+
+    /// A time `duration` from now.
+    pub fn from_now(duration: std::time::Duration) -> Self {
+        Self::seconds_from_now(duration.as_secs() as i64)
+    }
+
+    /// The time `then`, relative to `now`.
+    pub fn between<N: Into<DateTime>, T: Into<DateTime>>(now: N, then: T) -> Self {
+        Self::seconds_from_now(timestamp(&then.into()) - timestamp(&now.into()))
+    }
+
+    /// Set the length of the unit names.
+    pub fn with_style(mut self, style: RelativeStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set whether to use phrases like "yesterday" and "tomorrow".
+    pub fn with_numeric(mut self, numeric: RelativeNumeric) -> Self {
+        self.numeric = numeric;
+        self
+    }
+
+    /// Seconds from now (negative for the past).
+    pub fn seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    pub(crate) fn style(&self) -> RelativeStyle {
+        self.style
+    }
+
+    pub(crate) fn numeric(&self) -> RelativeNumeric {
+        self.numeric
+    }
+
+    /// The largest unit that fits, and the (rounded) number of those units.
+    pub(crate) fn unit(&self) -> (Unit, i64) {
+        let s = self.seconds;
+        if s.abs() < 60 {
+            return (Unit::Second, s);
+        }
+        let minutes = div_round(s, 60);
+        if minutes.abs() < 60 {
+            return (Unit::Minute, minutes);
+        }
+        let hours = div_round(s, 3600);
+        if hours.abs() < 24 {
+            return (Unit::Hour, hours);
+        }
+        let days = div_round(s, 86_400);
+        if days.abs() < 7 {
+            return (Unit::Day, days);
+        }
+        let weeks = div_round(s, 7 * 86_400);
+        if weeks.abs() < 4 {
+            return (Unit::Week, weeks);
+        }
+        // Average Gregorian month and year
+        let months = div_round(s, 2_629_746);
+        if months.abs() < 12 {
+            return (Unit::Month, months);
+        }
+        (Unit::Year, div_round(s, 31_556_952))
+    }
+}
+
+/// The catalog field names for `unit` in `style`, most specific first.
+pub(crate) fn fields(unit: Unit, style: RelativeStyle) -> Vec<String> {
+    let unit = unit.as_str();
+    match style {
+        RelativeStyle::Long => vec![unit.into()],
+        RelativeStyle::Short => vec![format!("{}-short", unit), unit.into()],
+        RelativeStyle::Narrow => {
+            vec![format!("{}-narrow", unit), format!("{}-short", unit), unit.into()]
+        }
+    }
+}
+
+/// Bundled English phrase for `-1`, `0` or `1` units.
+pub(crate) fn english_phrase(unit: Unit, style: RelativeStyle, value: i64) -> Option<&'static str> {
+    let short = style != RelativeStyle::Long;
+    let (last, this, next) = match (unit, short) {
+        (Unit::Second, _) => return if value == 0 { Some("now") } else { None },
+        (Unit::Minute, _) => return if value == 0 { Some("this minute") } else { None },
+        (Unit::Hour, _) => return if value == 0 { Some("this hour") } else { None },
+        (Unit::Day, _) => ("yesterday", "today", "tomorrow"),
+        (Unit::Week, false) => ("last week", "this week", "next week"),
+        (Unit::Week, true) => ("last wk.", "this wk.", "next wk."),
+        (Unit::Month, false) => ("last month", "this month", "next month"),
+        (Unit::Month, true) => ("last mo.", "this mo.", "next mo."),
+        (Unit::Year, false) => ("last year", "this year", "next year"),
+        (Unit::Year, true) => ("last yr.", "this yr.", "next yr."),
+    };
+    match value {
+        -1 => Some(last),
+        0 => Some(this),
+        1 => Some(next),
+        _ => None,
+    }
+}
+
+/// Bundled English pattern (with a `{0}` placeholder) for a number of units.
+pub(crate) fn english_pattern(unit: Unit, style: RelativeStyle, past: bool, one: bool) -> String {
+    let name = match (style, unit) {
+        (RelativeStyle::Long, unit) => {
+            let name = unit.as_str();
+            return match (past, one) {
+                (true, true) => format!("{{0}} {} ago", name),
+                (true, false) => format!("{{0}} {}s ago", name),
+                (false, true) => format!("in {{0}} {}", name),
+                (false, false) => format!("in {{0}} {}s", name),
+            };
+        }
+        (RelativeStyle::Short, Unit::Second) => " sec.",
+        (RelativeStyle::Short, Unit::Minute) => " min.",
+        (RelativeStyle::Short, Unit::Hour) => " hr.",
+        (RelativeStyle::Short, Unit::Day) if one => " day",
+        (RelativeStyle::Short, Unit::Day) => " days",
+        (RelativeStyle::Short, Unit::Week) => " wk.",
+        (RelativeStyle::Short, Unit::Month) => " mo.",
+        (RelativeStyle::Short, Unit::Year) => " yr.",
+        (RelativeStyle::Narrow, Unit::Second) => "s",
+        (RelativeStyle::Narrow, Unit::Minute) => "m",
+        (RelativeStyle::Narrow, Unit::Hour) => "h",
+        (RelativeStyle::Narrow, Unit::Day) => "d",
+        (RelativeStyle::Narrow, Unit::Week) => "w",
+        (RelativeStyle::Narrow, Unit::Month) => "mo",
+        (RelativeStyle::Narrow, Unit::Year) => "y",
+    };
+    if past {
+        format!("{{0}}{} ago", name)
+    } else {
+        format!("in {{0}}{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{english_pattern, english_phrase, RelativeStyle::*, RelativeTime, Unit};
+
+    #[test]
+    fn units() {
+        let unit = |s| RelativeTime::seconds_from_now(s).unit();
+
+        assert_eq!(unit(0), (Unit::Second, 0));
+        assert_eq!(unit(-59), (Unit::Second, -59));
+        assert_eq!(unit(90), (Unit::Minute, 2));
+        assert_eq!(unit(-3 * 3600), (Unit::Hour, -3));
+        assert_eq!(unit(-86_400 - 3600), (Unit::Day, -1));
+        assert_eq!(unit(10 * 86_400), (Unit::Week, 1));
+        assert_eq!(unit(28 * 86_400), (Unit::Month, 1));
+        assert_eq!(unit(-400 * 86_400), (Unit::Year, -1));
+
+        assert_eq!(english_pattern(Unit::Minute, Long, true, false), "{0} minutes ago");
+        assert_eq!(english_pattern(Unit::Day, Short, false, true), "in {0} day");
+        assert_eq!(english_pattern(Unit::Hour, Narrow, true, false), "{0}h ago");
+        assert_eq!(english_phrase(Unit::Day, Long, -1), Some("yesterday"));
+        assert_eq!(english_phrase(Unit::Month, Short, 1), Some("next mo."));
+        assert_eq!(english_phrase(Unit::Hour, Long, 1), None);
+    }
+}
@@ -0,0 +1,283 @@
+//! Rich text: messages with `<tag>...</tag>` markup, split into segments for the caller to
+//! render.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use super::err;
+
+/// A piece of a rich text message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RichSegment {
+    /// Plain text from the message
+    Text(String),
+    /// `<name>...</name>` (or `<name/>`, without children)
+    Tag { name: String, children: Vec<RichSegment> },
+    /// An interpolated variable
+    Var { name: String, value: String },
+}
+
+/// A piece of a message formatted for `translate_rich`
+#[derive(Debug)]
+enum Piece {
+    Text(String),
+    Var { name: String, value: String },
+}
+
+/// A message formatted for `translate_rich`: the text is written to `&Pieces`, and variables are
+/// added with `var`, so their values are never parsed for markup.
+#[derive(Debug, Default)]
+pub(crate) struct Pieces(Mutex<Vec<Piece>>);
+
+impl Pieces {
+    fn lock(&self) -> MutexGuard<'_, Vec<Piece>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add the variable `name`, with the value written by `write`.
+    pub(crate) fn var<F>(&self, name: &str, write: F) -> err::Result<()>
+    where
+        F: FnOnce(&mut dyn fmt::Write) -> err::Result<()>,
+    {
+        let mut value = String::new();
+        write(&mut value)?;
+        self.lock().push(Piece::Var { name: name.into(), value });
+        Ok(())
+    }
+}
+
+impl fmt::Write for &Pieces {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut pieces = self.lock();
+        match pieces.last_mut() {
+            Some(Piece::Text(text)) => text.push_str(s),
+            _ => pieces.push(Piece::Text(s.into())),
+        }
+        Ok(())
+    }
+}
+
+fn is_tag_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// If `text` starts with a tag, get `(name, closing, self_closing, length)`.
+fn tag(text: &str) -> Option<(&str, bool, bool, usize)> {
+    let end = text.find('>')?;
+    let inner = &text[1..end];
+    let (inner, closing) = match inner.strip_prefix('/') {
+        Some(inner) => (inner, true),
+        None => (inner, false),
+    };
+    let (inner, self_closing) = match inner.strip_suffix('/') {
+        Some(inner) if !closing => (inner, true),
+        _ => (inner, false),
+    };
+    if is_tag_name(inner) {
+        Some((inner, closing, self_closing, end + 1))
+    } else {
+        None
+    }
+}
+
+struct Parser {
+    pieces: std::vec::IntoIter<Piece>,
+    /// The text piece being parsed, and the position in it
+    text: String,
+    pos: usize,
+}
+
+impl Parser {
+    /// Parse segments up to the end of the message, or the closing tag for `open`.
+    fn segments(&mut self, open: Option<&str>) -> err::Result<Vec<RichSegment>> {
+        let mut out = Vec::new();
+        let mut text = String::new();
+
+        macro_rules! flush {
+            () => {
+                if !text.is_empty() {
+                    out.push(RichSegment::Text(std::mem::take(&mut text)));
+                }
+            };
+        }
+
+        loop {
+            let c = match self.text[self.pos..].chars().next() {
+                Some(c) => c,
+                None => match self.pieces.next() {
+                    Some(Piece::Text(next)) => {
+                        self.text = next;
+                        self.pos = 0;
+                        continue;
+                    }
+                    Some(Piece::Var { name, value }) => {
+                        flush!();
+                        out.push(RichSegment::Var { name, value });
+                        continue;
+                    }
+                    None => break,
+                },
+            };
+            let rest = &self.text[self.pos..];
+            match c {
+                '<' => match tag(rest) {
+                    Some((name, true, _, len)) => {
+                        if open != Some(name) {
+                            return Err(err::custom(format!("Unmatched tag `</{}>`", name)));
+                        }
+                        self.pos += len;
+                        flush!();
+                        return Ok(out);
+                    }
+                    Some((name, false, self_closing, len)) => {
+                        let name = String::from(name);
+                        self.pos += len;
+                        flush!();
+                        let children =
+                            if self_closing { Vec::new() } else { self.segments(Some(&name))? };
+                        out.push(RichSegment::Tag { name, children });
+                    }
+                    None => {
+                        text.push(c);
+                        self.pos += c.len_utf8();
+                    }
+                },
+                c => {
+                    text.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+
+        match open {
+            Some(name) => Err(err::custom(format!("Unclosed tag `<{}>`", name))),
+            None => {
+                flush!();
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// Split a formatted message into segments.
+pub(crate) fn parse(pieces: Pieces) -> err::Result<Vec<RichSegment>> {
+    let pieces = pieces.0.into_inner().unwrap_or_else(PoisonError::into_inner);
+    Parser { pieces: pieces.into_iter(), text: String::new(), pos: 0 }.segments(None)
+}
+
+/// Tag callback: `rendered children -> output`
+type TagFn<'f> = dyn Fn(&str) -> String + 'f;
+
+/// Renders `RichSegment`s to a `String` with a closure for each tag
+///
+/// Text and variables are written as is, and tags without a closure render only their children.
+///
+/// ```rust
+/// use loon::{RichSegment, TagRenderer};
+/// let segments = vec![
+///     RichSegment::Text("Read our ".into()),
+///     RichSegment::Tag { name: "b".into(), children: vec![RichSegment::Text("terms".into())] },
+/// ];
+/// let out = TagRenderer::new().tag("b", |s| format!("**{}**", s)).render(&segments);
+/// assert_eq!(out, "Read our **terms**");
+/// ```
+#[derive(Default)]
+pub struct TagRenderer<'f> {
+    tags: HashMap<String, Box<TagFn<'f>>>,
+}
+
+impl<'f> TagRenderer<'f> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render `<name>` tags with `render`, which gets the rendered children.
+    pub fn tag<I, F>(mut self, name: I, render: F) -> Self
+    where
+        I: Into<String>,
+        F: Fn(&str) -> String + 'f,
+    {
+        self.tags.insert(name.into(), Box::new(render));
+        self
+    }
+
+    /// Render `segments` to a `String`.
+    pub fn render(&self, segments: &[RichSegment]) -> String {
+        let mut out = String::new();
+        for segment in segments {
+            match segment {
+                RichSegment::Text(text) => out.push_str(text),
+                RichSegment::Var { value, .. } => out.push_str(value),
+                RichSegment::Tag { name, children } => {
+                    let children = self.render(children);
+                    match self.tags.get(name) {
+                        Some(render) => out.push_str(&render(&children)),
+                        None => out.push_str(&children),
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{parse, Pieces, RichSegment::*};
+    use std::fmt::Write;
+
+    fn pieces(text: &str) -> Pieces {
+        let pieces = Pieces::default();
+        (&pieces).write_str(text).unwrap();
+        pieces
+    }
+
+    #[test]
+    fn parses() {
+        let message = pieces("Hi ");
+        message.var("name", |out| Ok(out.write_str("<Jacob>\u{e000}</b>")?)).unwrap();
+        (&message)
+            .write_str(", read our <link>terms</link> and <b>privacy <i>policy</i></b><br/> 1 < 2")
+            .unwrap();
+
+        assert_eq!(
+            parse(message).unwrap(),
+            vec![
+                Text("Hi ".into()),
+                Var { name: "name".into(), value: "<Jacob>\u{e000}</b>".into() },
+                Text(", read our ".into()),
+                Tag { name: "link".into(), children: vec![Text("terms".into())] },
+                Text(" and ".into()),
+                Tag {
+                    name: "b".into(),
+                    children: vec![
+                        Text("privacy ".into()),
+                        Tag { name: "i".into(), children: vec![Text("policy".into())] }
+                    ]
+                },
+                Tag { name: "br".into(), children: vec![] },
+                Text(" 1 < 2".into()),
+            ]
+        );
+
+        // Variables can be inside tags
+        let message = pieces("<b>");
+        message.var("name", |out| Ok(out.write_str("Jo")?)).unwrap();
+        (&message).write_str("</b>").unwrap();
+        assert_eq!(
+            parse(message).unwrap(),
+            vec![Tag {
+                name: "b".into(),
+                children: vec![Var { name: "name".into(), value: "Jo".into() }]
+            }]
+        );
+
+        assert!(parse(pieces("<b>open")).is_err());
+        assert!(parse(pieces("close</b>")).is_err());
+        assert!(parse(pieces("<b>crossed</i></b>")).is_err());
+    }
+}
@@ -0,0 +1,483 @@
+//! Typed variables, formatted for the locale of the `translate` call.
+
+use std::convert::TryFrom;
+
+use super::{
+    currency::{self, CurrencyStyle, Money},
+    err,
+    html::{self, Escape},
+    list,
+    number::{self, Decimal, NumberStyle},
+    DateTime, ListType,
+};
+
+/// The value of an interpolated variable
+///
+/// Values keep their type until the message is formatted, so that loon can format numbers,
+/// dates and lists for the locale of the call, and select plural forms (or `select` branches)
+/// by any variable.
+///
+/// Built with `From` for strings, `char`, `bool`, primitive integers and floats, `Decimal`,
+/// `DateTime` and `Money`:
+///
+/// ```rust
+/// use loon::{DateTime, ListType, Value};
+/// assert_eq!(Value::from(42), Value::Integer(42));
+/// assert_eq!(Value::from("Jacob"), Value::String("Jacob".into()));
+/// assert_eq!(Value::from(DateTime::date(2020, 3, 4)).to_string(), "2020-03-04");
+/// assert_eq!(Value::list(vec!["a", "b"], ListType::Conjunction).to_string(), "a, b");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// Written as is (`1234`), or formatted with a number hint
+    Integer(i64),
+    /// Written with its visible fraction digits (`1.50`), or formatted with a number hint
+    Decimal(Decimal),
+    String(String),
+    /// Localized with the `default` date (or time) format, or the format in a `date` hint
+    Date(DateTime),
+    /// Formatted in its currency for the locale, with or without a `currency` hint
+    Money(Money),
+    /// Joined with the list pattern of the locale, e.g. "A, B, and C"
+    List(Vec<String>, ListType),
+    /// The message for another key, translated in the same locale
+    Translation(String),
+}
+
+impl Value {
+    /// A list of `items`, joined with the list pattern of the locale.
+    pub fn list<L, S>(items: L, list_type: ListType) -> Self
+    where
+        L: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Value::List(items.into_iter().map(Into::into).collect(), list_type)
+    }
+
+    /// The message for `key`, translated in the locale of the call.
+    pub fn translation<K: Into<String>>(key: K) -> Self {
+        Value::Translation(key.into())
+    }
+
+    /// The value as a number, if it is one (or a string that parses as one).
+    pub(crate) fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Integer(n) => Some(Decimal::from(*n)),
+            Value::Decimal(n) => Some(*n),
+            Value::Money(money) => Some(money.amount),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Not localized: dates are ISO 8601, lists are joined with `, `, and translations show their key.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Decimal(n) => write!(f, "{}", n),
+            Value::String(s) | Value::Translation(s) => f.write_str(s),
+            Value::Date(date) => {
+                write!(f, "{:04}-{:02}-{:02}", date.year(), date.month(), date.day())?;
+                if date.has_time() {
+                    write!(f, " {:02}:{:02}:{:02}", date.hour(), date.minute(), date.second())?;
+                }
+                Ok(())
+            }
+            Value::List(items, _) => f.write_str(&items.join(", ")),
+            Value::Money(money) => write!(f, "{}", money),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(t: &str) -> Self {
+        Value::String(t.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(t: String) -> Self {
+        Value::String(t)
+    }
+}
+
+impl From<&String> for Value {
+    fn from(t: &String) -> Self {
+        Value::String(t.clone())
+    }
+}
+
+impl From<std::borrow::Cow<'_, str>> for Value {
+    fn from(t: std::borrow::Cow<'_, str>) -> Self {
+        Value::String(t.into_owned())
+    }
+}
+
+impl From<char> for Value {
+    fn from(t: char) -> Self {
+        Value::String(t.into())
+    }
+}
+
+/// `true` or `false`, e.g. for `select`.
+impl From<bool> for Value {
+    fn from(t: bool) -> Self {
+        Value::String(t.to_string())
+    }
+}
+
+macro_rules! value_from_int {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(t: $t) -> Self {
+                    Value::Integer(i64::from(t))
+                }
+            }
+        )*
+    };
+}
+
+value_from_int!(i8 i16 i32 i64 u8 u16 u32);
+
+macro_rules! value_from_large_int {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(t: $t) -> Self {
+                    match i64::try_from(t) {
+                        Ok(n) => Value::Integer(n),
+                        Err(_) => Value::Decimal(Decimal::from(t)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+value_from_large_int!(i128 u64 u128 isize usize);
+
+impl From<f32> for Value {
+    fn from(t: f32) -> Self {
+        Value::Decimal(t.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(t: f64) -> Self {
+        Value::Decimal(t.into())
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(t: Decimal) -> Self {
+        Value::Decimal(t)
+    }
+}
+
+impl From<DateTime> for Value {
+    fn from(t: DateTime) -> Self {
+        Value::Date(t)
+    }
+}
+
+impl From<Money> for Value {
+    fn from(t: Money) -> Self {
+        Value::Money(t)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for Value {
+    fn from(t: chrono::NaiveDate) -> Self {
+        Value::Date(t.into())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDateTime> for Value {
+    fn from(t: chrono::NaiveDateTime) -> Self {
+        Value::Date(t.into())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for Value {
+    fn from(t: chrono::DateTime<Tz>) -> Self {
+        Value::Date(t.into())
+    }
+}
+
+/// Add the variables in `json` to `vars`, with names under `prefix`: object entries and array
+/// items become `prefix.key` and `prefix.0` variables, and arrays of strings or numbers are also
+/// lists. `null`s are skipped.
+pub(crate) fn flatten(prefix: &str, json: serde_json::Value, vars: &mut VarMap) {
+    let name = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+    let value = match json {
+        serde_json::Value::Null => return,
+        serde_json::Value::Bool(b) => Value::from(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(n) => Value::Integer(n),
+            None => Value::Decimal(
+                n.to_string().parse().unwrap_or_else(|_| Decimal::from(n.as_f64().unwrap_or(0.0))),
+            ),
+        },
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Array(items) => {
+            let list = items
+                .iter()
+                .map(|item| match item {
+                    serde_json::Value::String(s) => Some(s.clone()),
+                    serde_json::Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            for (idx, item) in items.into_iter().enumerate() {
+                flatten(&name(&idx.to_string()), item, vars);
+            }
+            match list {
+                Some(list) if !prefix.is_empty() => Value::List(list, ListType::default()),
+                _ => return,
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map {
+                flatten(&name(&key), item, vars);
+            }
+            return;
+        }
+    };
+    vars.insert(prefix.to_string(), value);
+}
+
+/// The variables of a `translate` call
+///
+/// Calls have a handful of variables, so a `Vec` with linear lookups is cheaper to build and
+/// search than a `HashMap`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct VarMap(Vec<(String, Value)>);
+
+impl VarMap {
+    /// Set the variable `name`, replacing any previous value.
+    pub(crate) fn insert(&mut self, name: String, value: Value) {
+        match self.0.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Value> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A formatting hint in a placeholder, e.g. `{total, number}`, `{price, currency}` or
+/// `{joined, date, long}`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Hint {
+    Number(NumberStyle),
+    /// Currency, with an optional ISO 4217 code (otherwise taken from a `Money` value)
+    Currency(Option<String>, CurrencyStyle),
+    /// Date or time, with a named format of the locale (`default`, `short`, ...) or a `strftime`
+    /// pattern
+    Date(String),
+}
+
+impl Hint {
+    /// Parse a placeholder hint: `number` (with an optional `integer`, `percent`, `compact` or
+    /// `currency` style), `integer`, `percent`, `compact`, `currency` (with an optional ISO
+    /// 4217 code and/or `accounting`), or `date` / `time` (with an optional format).
+    pub(crate) fn parse(kind: &str, style: Option<&str>) -> Option<Self> {
+        match (kind, style) {
+            ("number", None) => Some(Hint::Number(NumberStyle::Decimal)),
+            ("number", Some("integer")) | ("integer", None) => {
+                Some(Hint::Number(NumberStyle::Integer))
+            }
+            ("number", Some("percent")) | ("percent", None) => {
+                Some(Hint::Number(NumberStyle::Percent))
+            }
+            ("number", Some("compact")) | ("compact", None) => {
+                Some(Hint::Number(NumberStyle::Compact))
+            }
+            ("number", Some("currency")) => Some(Hint::Currency(None, CurrencyStyle::Standard)),
+            ("number", Some(style)) => style
+                .strip_prefix("::currency/")
+                .filter(|code| currency::is_code(code))
+                .map(|code| Hint::Currency(Some(code.into()), CurrencyStyle::Standard)),
+            ("currency", style) => {
+                let mut code = None;
+                let mut currency_style = CurrencyStyle::Standard;
+                for part in style.into_iter().flat_map(|style| style.split(',')).map(str::trim) {
+                    match part {
+                        "accounting" => currency_style = CurrencyStyle::Accounting,
+                        part if currency::is_code(part) => code = Some(part.into()),
+                        _ => return None,
+                    }
+                }
+                Some(Hint::Currency(code, currency_style))
+            }
+            ("date", style) | ("time", style) => {
+                Some(Hint::Date(style.map(str::trim).unwrap_or("default").into()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Date localization: `(value, format) -> localized`, with a named format or `strftime` pattern
+pub(crate) type LocalizeFn<'a> = dyn Fn(&DateTime, &str) -> err::Result<String> + 'a;
+
+/// Nested translation: `key -> message`, in the locale of the call (and as HTML for
+/// `translate_html`)
+pub(crate) type TranslateFn<'a> = dyn Fn(&str) -> err::Result<String> + 'a;
+
+/// The variables of a `translate` call, formatted for its locale
+pub(crate) struct Vars<'a> {
+    pub(crate) locale: &'a str,
+    pub(crate) values: &'a VarMap,
+    pub(crate) localize: &'a LocalizeFn<'a>,
+    pub(crate) translate: &'a TranslateFn<'a>,
+    /// Formatted values are escaped unless `Escape::Nothing` (translations are already HTML)
+    pub(crate) escape: Escape,
+}
+
+impl Vars<'_> {
+    pub(crate) fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Format `value` for the locale, with an optional `hint`, or `None` if the value doesn't
+    /// fit the hint (e.g. a string with a `number` hint).
+    pub(crate) fn format(&self, value: &Value, hint: Option<&Hint>) -> err::Result<Option<String>> {
+        let out = match (hint, value) {
+            (None, Value::Translation(key)) => return (self.translate)(key).map(Some),
+            (None, Value::Date(date)) => (self.localize)(date, "default")?,
+            (None, Value::List(items, list_type)) => list::format(self.locale, *list_type, items),
+            (None, Value::Money(money)) => {
+                currency::format(self.locale, &money.amount, &money.currency, Default::default())
+            }
+            (None, value) => value.to_string(),
+            (Some(Hint::Number(style)), value) => match value.as_decimal() {
+                Some(n) => number::format(self.locale, &n, *style),
+                None => return Ok(None),
+            },
+            // The value's own currency wins over a code in the hint
+            (Some(Hint::Currency(_, style)), Value::Money(money)) => {
+                currency::format(self.locale, &money.amount, &money.currency, *style)
+            }
+            (Some(Hint::Currency(Some(code), style)), value) => match value.as_decimal() {
+                Some(amount) => currency::format(self.locale, &amount, code, *style),
+                None => return Ok(None),
+            },
+            (Some(Hint::Date(format)), Value::Date(date)) => (self.localize)(date, format)?,
+            (Some(_), _) => return Ok(None),
+        };
+        match self.escape {
+            Escape::Nothing => Ok(Some(out)),
+            _ => Ok(Some(html::escape(&out))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{flatten, Escape, Hint, Value, VarMap, Vars};
+    use crate::{err, DateTime, Decimal, ListType, Money, NumberStyle};
+
+    #[test]
+    fn formats() {
+        let localize = |value: &DateTime, format: &str| -> err::Result<String> {
+            Ok(format!("{}/{}/{} ({})", value.month(), value.day(), value.year(), format))
+        };
+        let translate = |key: &str| -> err::Result<String> { Ok(key.to_uppercase()) };
+        let values = VarMap::default();
+        let vars = Vars {
+            locale: "de",
+            values: &values,
+            localize: &localize,
+            translate: &translate,
+            escape: Escape::Nothing,
+        };
+        let format = |value: Value, hint: Option<Hint>| vars.format(&value, hint.as_ref()).unwrap();
+        let number = Some(Hint::Number(NumberStyle::Decimal));
+
+        assert_eq!(format(Value::from(1234), None).unwrap(), "1234");
+        assert_eq!(format(Value::from(1234), number.clone()).unwrap(), "1.234");
+        assert_eq!(format(Value::from(Decimal::new(150, 2)), None).unwrap(), "1.50");
+        assert_eq!(format(Value::from("1234.5"), number.clone()).unwrap(), "1.234,5");
+        assert_eq!(format(Value::from("many"), number), None);
+        assert_eq!(
+            format(Value::from(5), Some(Hint::Currency(Some("EUR".into()), Default::default())))
+                .unwrap(),
+            "5,00\u{a0}€"
+        );
+        assert_eq!(
+            format(Value::from(Money::new(5, "USD")), Hint::parse("currency", None)).unwrap(),
+            "5,00\u{a0}$"
+        );
+        assert_eq!(
+            format(Value::from(Money::new(5, "USD")), Hint::parse("currency", Some("accounting")))
+                .unwrap(),
+            "5,00\u{a0}$"
+        );
+        assert_eq!(format(Value::from(Money::new(-5, "USD")), None).unwrap(), "-5,00\u{a0}$");
+        assert_eq!(format(Value::from(5), Hint::parse("currency", None)), None);
+        assert_eq!(format(Value::from("5 USD"), Hint::parse("currency", None)), None);
+
+        let date = Value::from(DateTime::date(2020, 3, 4));
+        assert_eq!(format(date.clone(), None).unwrap(), "3/4/2020 (default)");
+        assert_eq!(format(date, Hint::parse("date", Some(" long"))).unwrap(), "3/4/2020 (long)");
+        assert_eq!(format(Value::from(true), Hint::parse("date", None)), None);
+
+        let list = Value::list(vec!["A", "B", "C"], ListType::Conjunction);
+        assert_eq!(format(list, None).unwrap(), "A, B und C");
+
+        assert_eq!(Value::from(u64::MAX), Value::Decimal(Decimal::from(u64::MAX)));
+        assert_eq!(Value::from(2.5).as_decimal(), Some(Decimal::new(25, 1)));
+        assert_eq!(Value::translation("app.name").as_decimal(), None);
+        assert_eq!(format(Value::translation("app.name"), None).unwrap(), "APP.NAME");
+
+        let html = Vars { escape: Escape::Values, ..vars };
+        assert_eq!(html.format(&Value::from("<b>"), None).unwrap().unwrap(), "&lt;b&gt;");
+        let translated = html.format(&Value::translation("<b>"), None).unwrap().unwrap();
+        assert_eq!(translated, "<B>");
+    }
+
+    #[test]
+    fn flattens() {
+        let json = serde_json::json!({
+            "user": { "name": "Sam", "age": 41, "admin": false, "email": null },
+            "tags": ["a", "b"],
+            "total": 12.50,
+        });
+        let mut vars = VarMap::default();
+        flatten("", json, &mut vars);
+
+        assert_eq!(vars.get("user.name"), Some(&Value::from("Sam")));
+        assert_eq!(vars.get("user.age"), Some(&Value::Integer(41)));
+        assert_eq!(vars.get("user.admin"), Some(&Value::from(false)));
+        assert_eq!(vars.get("user.email"), None);
+        assert_eq!(vars.get("tags"), Some(&Value::list(vec!["a", "b"], ListType::Conjunction)));
+        assert_eq!(vars.get("tags.1"), Some(&Value::from("b")));
+        assert_eq!(vars.get("total"), Some(&Value::Decimal(Decimal::new(125, 1))));
+        assert_eq!(vars.len(), 7);
+    }
+}